@@ -0,0 +1,72 @@
+//! A turn of the UI loop while nothing changed, a tick and the check
+//! whether to draw, as it happens many times a second: what it allocates
+//! and how long it takes by the number of interfaces.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tui_state_monitor::bench::{App, InterfaceState, Limits, NameFilter, Offscreen, StateValues};
+
+mod common;
+
+#[global_allocator]
+static ALLOCATOR: common::Counting = common::Counting;
+
+const INTERFACES: [usize; 2] = [200, 2000];
+// Turns measured at once, the allocations are reported per turn.
+const TURNS: usize = 100;
+
+fn offscreen(interfaces: usize) -> Offscreen {
+    let app = App::new(
+        NameFilter::default(),
+        StateValues::default(),
+        Limits::default(),
+    );
+    let app = (0..interfaces).fold(app, |app, i| {
+        app.update_state(InterfaceState {
+            name: format!("/robot/interface_{}", i),
+            interface_type: "server".to_string(),
+            state: "Active".to_string(),
+            stamp: None,
+            substates: None,
+            message: None,
+            tags: None,
+            raw: None,
+        })
+    });
+    let mut offscreen = Offscreen::new(vec![app], 160, 50).unwrap();
+    offscreen.draw().unwrap();
+    offscreen
+}
+
+fn turn(offscreen: &mut Offscreen) {
+    offscreen.tick();
+    offscreen.draw().unwrap();
+}
+
+fn idle(c: &mut Criterion) {
+    for interfaces in INTERFACES {
+        let mut offscreen = offscreen(interfaces);
+        let (_, allocated) = common::measure(|| {
+            for _ in 0..TURNS {
+                turn(&mut offscreen);
+            }
+        });
+        println!(
+            "an idle turn with {} interfaces allocates {:.2} times, {:.0} bytes",
+            interfaces,
+            allocated.allocations as f64 / TURNS as f64,
+            allocated.bytes as f64 / TURNS as f64
+        );
+    }
+
+    let mut group = c.benchmark_group("idle turn");
+    for interfaces in INTERFACES {
+        let mut offscreen = offscreen(interfaces);
+        group.bench_with_input(BenchmarkId::from_parameter(interfaces), &(), |b, ()| {
+            b.iter(|| turn(&mut offscreen))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, idle);
+criterion_main!(benches);
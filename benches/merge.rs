@@ -0,0 +1,152 @@
+//! Throughput of applying states from several producers into one app, as
+//! a topic and MQTT feed it, while the UI keeps reading it: each producer
+//! taking the lock for every message, as the subscribers used to, against
+//! queueing the parsed states to the one merger of the app. Reports how
+//! long the reader waited for the lock at most besides the time.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tui_state_monitor::bench::{self, App, Encoding, Limits, NameFilter, StateValues};
+
+const MESSAGES: usize = 20_000;
+const INTERFACES: usize = 500;
+const PRODUCERS: [usize; 2] = [1, 4];
+// How often the reader takes the lock, about as often as the UI draws.
+const READ_PERIOD: Duration = Duration::from_millis(5);
+
+fn messages(producer: usize, count: usize) -> Vec<Vec<u8>> {
+    (0..count)
+        .map(|i| {
+            let state = if i % 2 == 0 { "Active" } else { "Inactive" };
+            serde_json::json!({
+                "name": format!("/p{}/interface_{}", producer, i % INTERFACES),
+                "interface_type": "server",
+                "state": state,
+            })
+            .to_string()
+            .into_bytes()
+        })
+        .collect()
+}
+
+fn shared_app() -> Arc<RwLock<App>> {
+    Arc::new(RwLock::new(App::new(
+        NameFilter::default(),
+        StateValues::default(),
+        Limits::default(),
+    )))
+}
+
+/// Each message parsed and applied under one lock of its own.
+async fn locked_per_message(shared_app: Arc<RwLock<App>>, messages: Vec<Vec<u8>>) {
+    for data in messages {
+        {
+            let mut app = shared_app.write().unwrap();
+            app.stats.messages += 1;
+            let _ = Encoding::Json.parse_each(&data, |state| {
+                if let Ok(state) = state {
+                    *app = std::mem::take(&mut *app).update_state(state);
+                }
+            });
+        }
+        tokio::task::yield_now().await;
+    }
+}
+
+/// Reads the app as the UI does until all messages were applied, returns
+/// the longest wait for the lock.
+async fn read_until_applied(shared_app: Arc<RwLock<App>>, total: u64) -> Duration {
+    let mut longest = Duration::ZERO;
+    loop {
+        let asked = Instant::now();
+        let messages = {
+            let app = shared_app.read().unwrap();
+            longest = longest.max(asked.elapsed());
+            // The UI clones what it shows under the lock.
+            let shown = app.clone();
+            shown.stats.messages
+        };
+        if messages >= total {
+            return longest;
+        }
+        tokio::time::sleep(READ_PERIOD).await;
+    }
+}
+
+/// Runs `producers` feeding `MESSAGES` in all, returns how long it took and
+/// the longest wait of the reader.
+fn run(runtime: &tokio::runtime::Runtime, producers: usize, queued: bool) -> (Duration, Duration) {
+    let feeds: Vec<_> = (0..producers)
+        .map(|producer| messages(producer, MESSAGES / producers))
+        .collect();
+    let total = (MESSAGES / producers * producers) as u64;
+    runtime.block_on(async move {
+        let shared_app = shared_app();
+        let started = Instant::now();
+        let reader = tokio::task::spawn(read_until_applied(shared_app.clone(), total));
+        let mut tasks = Vec::new();
+        for feed in feeds {
+            tasks.push(if queued {
+                bench::spawn_pipeline(futures::stream::iter(feed), &shared_app)
+            } else {
+                tokio::task::spawn(locked_per_message(shared_app.clone(), feed))
+            });
+        }
+        let longest_wait = reader.await.unwrap();
+        let elapsed = started.elapsed();
+        for task in tasks {
+            task.abort();
+        }
+        (elapsed, longest_wait)
+    })
+}
+
+fn merge(c: &mut Criterion) {
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(workers.max(2))
+        .enable_all()
+        .build()
+        .unwrap();
+    println!("{} messages on {} cores", MESSAGES, workers);
+    for producers in PRODUCERS {
+        for (label, queued) in [
+            ("locked per message", false),
+            ("queued to the merger", true),
+        ] {
+            let (elapsed, longest_wait) = run(&runtime, producers, queued);
+            println!(
+                "{} producers, {}: {:.0} messages/s, the reader waited at most {:?}",
+                producers,
+                label,
+                MESSAGES as f64 / elapsed.as_secs_f64(),
+                longest_wait
+            );
+        }
+    }
+
+    let mut group = c.benchmark_group("merge");
+    group.throughput(Throughput::Elements(MESSAGES as u64));
+    group.sample_size(10);
+    for producers in PRODUCERS {
+        for (label, queued) in [("locked per message", false), ("queued", true)] {
+            group.bench_with_input(
+                BenchmarkId::new(label, producers),
+                &producers,
+                |b, &producers| {
+                    b.iter_custom(|iterations| {
+                        (0..iterations)
+                            .map(|_| run(&runtime, producers, queued).0)
+                            .sum()
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, merge);
+criterion_main!(benches);
@@ -0,0 +1,96 @@
+//! Parsing and applying a batch of 10k states, as a message holding an array of them:
+//! handed over one at a time while the batch is parsed, against decoding
+//! the whole batch first and applying it then, with the time and the most memory either holds.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tui_state_monitor::bench::{App, Encoding, InterfaceState, Limits, NameFilter, StateValues};
+
+mod common;
+
+#[global_allocator]
+static ALLOCATOR: common::Counting = common::Counting;
+
+const BATCH: usize = 10_000;
+const TYPES: [&str; 3] = ["server", "publisher", "subscriber"];
+const STATES: [&str; 3] = ["Active", "Inactive", "Error"];
+
+fn batch() -> Vec<u8> {
+    let states: Vec<serde_json::Value> = (0..BATCH)
+        .map(|i| {
+            serde_json::json!({
+                "name": format!("/robot/interface_{}", i),
+                "interface_type": TYPES[i % TYPES.len()],
+                "state": STATES[i % STATES.len()],
+                "message": "some text a publisher sends along with its state",
+            })
+        })
+        .collect();
+    serde_json::to_vec(&states).unwrap()
+}
+
+fn app() -> App {
+    App::new(
+        NameFilter::default(),
+        StateValues::default(),
+        Limits::default(),
+    )
+}
+
+/// As the subscriber and the merger do, each state is applied as soon as
+/// it is parsed.
+fn streamed(data: &[u8]) -> App {
+    let mut app = app();
+    Encoding::Json
+        .parse_each(data, |state| apply(&mut app, state))
+        .unwrap();
+    app
+}
+
+/// The batch decoded as a whole before any state is applied.
+fn decoded_first(data: &[u8]) -> App {
+    let value = Encoding::Json.decode(data).unwrap();
+    let mut states = Vec::new();
+    InterfaceState::parse_each(value, |state| states.push(state)).unwrap();
+    let mut app = app();
+    for state in states {
+        apply(&mut app, state);
+    }
+    app
+}
+
+/// As the merger applies a state.
+fn apply(app: &mut App, state: Result<InterfaceState, String>) {
+    if let Ok(state) = state {
+        *app = std::mem::take(app).update_state(state);
+    }
+}
+
+fn parse(c: &mut Criterion) {
+    let data = batch();
+    for (label, parse) in [
+        ("streamed", streamed as fn(&[u8]) -> App),
+        ("decoded first", decoded_first),
+    ] {
+        let (app, allocated) = common::measure(|| parse(&data));
+        assert_eq!(app.stats.interfaces_seen, BATCH as u64);
+        println!(
+            "{} batch of {} states, {} bytes: {} allocations of {} bytes, at most {} live",
+            label,
+            BATCH,
+            data.len(),
+            allocated.allocations,
+            allocated.bytes,
+            allocated.peak
+        );
+    }
+
+    let mut group = c.benchmark_group("parse 10k batch");
+    group.throughput(Throughput::Elements(BATCH as u64));
+    group.sample_size(20);
+    group.bench_function("streamed", |b| b.iter(|| streamed(&data)));
+    group.bench_function("decoded first", |b| b.iter(|| decoded_first(&data)));
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+//! What drawing a frame costs: the bytes written to the terminal when
+//! nothing changed, after a forced redraw and after one state changed, and
+//! how long each of those draws takes.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use tui_state_monitor::bench::{App, InterfaceState, Limits, NameFilter, Offscreen, StateValues};
+
+mod common;
+
+#[global_allocator]
+static ALLOCATOR: common::Counting = common::Counting;
+
+const INTERFACES: usize = 200;
+
+fn state(name: usize, state: &str) -> InterfaceState {
+    InterfaceState {
+        name: format!("/robot/interface_{}", name),
+        interface_type: "server".to_string(),
+        state: state.to_string(),
+        stamp: None,
+        substates: None,
+        message: None,
+        tags: None,
+        raw: None,
+    }
+}
+
+fn apps() -> Vec<App> {
+    let app = App::new(
+        NameFilter::default(),
+        StateValues::default(),
+        Limits::default(),
+    );
+    vec![(0..INTERFACES).fold(app, |app, i| app.update_state(state(i, "Active")))]
+}
+
+fn offscreen() -> Offscreen {
+    let mut offscreen = Offscreen::new(apps(), 160, 50).unwrap();
+    offscreen.draw().unwrap();
+    offscreen
+}
+
+fn bytes_per_frame() {
+    let mut offscreen = Offscreen::new(apps(), 160, 50).unwrap();
+    let first = offscreen.draw().unwrap().len();
+    let unchanged = offscreen.draw().unwrap().len();
+    offscreen.resize();
+    let resized = offscreen.draw().unwrap().len();
+    offscreen.tick();
+    let ticked = offscreen.draw().unwrap().len();
+    let mut changed = apps();
+    changed[0] = changed[0].clone().update_state(state(3, "Error"));
+    offscreen.set_apps(changed);
+    let changed = offscreen.draw().unwrap().len();
+    println!(
+        "bytes per frame of {} interfaces on 160x50: first {}, unchanged {}, \
+         after a resize {}, after a tick {}, one state changed {}",
+        INTERFACES, first, unchanged, resized, ticked, changed
+    );
+}
+
+fn render(c: &mut Criterion) {
+    bytes_per_frame();
+    let mut offscreen = offscreen();
+    let (_, allocated) = common::measure(|| {
+        offscreen.resize();
+        offscreen.draw().unwrap()
+    });
+    println!(
+        "a forced redraw allocates {} times, {} bytes, at most {} live at once",
+        allocated.allocations, allocated.bytes, allocated.peak
+    );
+
+    c.bench_function("render/forced redraw, unchanged", |b| {
+        b.iter(|| {
+            offscreen.resize();
+            offscreen.draw().unwrap()
+        })
+    });
+    c.bench_function("render/first frame", |b| {
+        b.iter_batched(
+            || Offscreen::new(apps(), 160, 50).unwrap(),
+            |mut offscreen| offscreen.draw().unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
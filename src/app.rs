@@ -0,0 +1,947 @@
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use crate::config::{Eviction, Limits, SeverityOrder, StateValues};
+use crate::encoding::Encoding;
+use crate::graph::Presence;
+use crate::link::{Arrivals, Pace, Rate};
+use crate::name_filter::NameFilter;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InterfaceState {
+    pub name: String,
+    // Left out by sparse updates of a known interface, then empty and taken
+    // from the interface of that name.
+    #[serde(default)]
+    pub interface_type: String,
+    // A discrete state such as "Active", or a number such as a battery percentage.
+    #[serde(deserialize_with = "string_or_number")]
+    pub state: String,
+    // Seconds since the unix epoch, set by the publisher when the state was sampled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stamp: Option<f64>,
+    // Named parts of a composite interface, e.g. {"comm": "ok", "power": "warn"}.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub substates: Option<BTreeMap<String, String>>,
+    // Human-readable context for the state, e.g. "reconnecting to camera".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    // Categories of the interface, e.g. ["critical", "camera"], to filter by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    // The state object as it was received, for inspecting odd payloads.
+    #[serde(skip)]
+    pub raw: Option<Arc<serde_json::Value>>,
+}
+
+/// Reads a number as its decimal text, so it is kept and shown as reported.
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(value) => Ok(value),
+        serde_json::Value::Number(value) => Ok(value.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "invalid state {}, expected a string or a number",
+            other
+        ))),
+    }
+}
+
+/// The number a state value stands for, if it is a numeric one.
+pub fn parse_number(value: &str) -> Option<f64> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+}
+
+// Fields a message has to carry to be shown at all.
+const REQUIRED_FIELDS: [&str; 2] = ["name", "state"];
+
+impl InterfaceState {
+    /// Parses a message holding one state or an array of them, a batch, and
+    /// hands each state to `each` as soon as it is parsed so that a large
+    /// batch is never held in memory as a whole. A state that can't be used
+    /// is handed over as an error and the rest of the batch still applies,
+    /// only a malformed message stops the parsing. Works in any encoding.
+    pub fn parse_each<'de, D: Deserializer<'de>>(
+        deserializer: D,
+        each: impl FnMut(Result<InterfaceState, String>),
+    ) -> Result<(), D::Error> {
+        deserializer.deserialize_any(EachState(each))
+    }
+
+    /// Converts one parsed state, ignoring fields it doesn't know. A state
+    /// without one of the required fields is rejected naming that field, so
+    /// that a publisher that renamed it can be told apart from one sending garbage.
+    /// So is an empty interface type, which no column could be titled by.
+    /// A left out one is not the same: it makes a sparse update of a known
+    /// interface, which keeps its type, and `update_state` counts it as a
+    /// parse error only when there is no such interface yet.
+    fn from_value(value: serde_json::Value) -> Result<InterfaceState, String> {
+        let Some(object) = value.as_object() else {
+            return Err("not an object".to_string());
+        };
+        if let Some(field) = REQUIRED_FIELDS.iter().find(|f| !object.contains_key(**f)) {
+            return Err(format!("missing field '{}'", field));
+        }
+        let has_type = object.contains_key("interface_type");
+        let mut state = InterfaceState::deserialize(&value).map_err(|e| e.to_string())?;
+        if has_type && state.interface_type.trim().is_empty() {
+            return Err("empty field 'interface_type'".to_string());
+        }
+        state.raw = Some(Arc::new(value));
+        Ok(state)
+    }
+}
+
+/// Visits a message, one state at a time.
+struct EachState<F>(F);
+
+impl<'de, F: FnMut(Result<InterfaceState, String>)> Visitor<'de> for EachState<F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a state object or an array of them")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, map: A) -> Result<(), A::Error> {
+        let value = serde_json::Value::deserialize(MapAccessDeserializer::new(map))?;
+        (self.0)(InterfaceState::from_value(value));
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(value) = seq.next_element::<serde_json::Value>()? {
+            (self.0)(InterfaceState::from_value(value));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum State {
+    Active,
+    #[default]
+    Inactive,
+    // Inactive, but for an interface that is normally idle.
+    Idle,
+    Warning,
+    Error,
+    Unknown,
+}
+
+impl State {
+    pub fn health(self) -> Health {
+        match self {
+            State::Active => Health::Ok,
+            State::Idle => Health::Idle,
+            State::Unknown => Health::Unknown,
+            State::Warning => Health::Warn,
+            State::Inactive | State::Error => Health::Error,
+        }
+    }
+}
+
+/// How healthy an interface or one of its substates is, ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Health {
+    Ok,
+    Idle,
+    Unknown,
+    Warn,
+    Error,
+}
+
+impl Health {
+    pub fn of_substate(value: &str) -> Health {
+        match value.to_lowercase().as_str() {
+            "ok" | "active" | "up" => Health::Ok,
+            "warn" | "warning" | "degraded" => Health::Warn,
+            _ => Health::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Interface {
+    // "server", "publisher" or "subscriber".
+    pub interface_type: &'static str,
+    pub state: State,
+    // The state exactly as reported, before it was classified.
+    pub value: String,
+    pub stamp: Option<f64>,
+    pub updated: Instant,
+    // When the interface was first observed, kept across updates.
+    pub first_seen: Instant,
+    // When the reported state last changed, kept across updates that repeat it.
+    pub changed: Instant,
+    pub substates: BTreeMap<String, String>,
+    pub message: Option<String>,
+    pub tags: Vec<String>,
+    // The last state object received for it, shared by the copies of the app.
+    pub raw: Option<Arc<serde_json::Value>>,
+    // False for an expected interface that hasn't reported a state yet.
+    pub reported: bool,
+    // When its updates arrived, and how often they should by `[[states.rates]]`.
+    pub arrivals: Option<Arrivals>,
+    pub expected_rate: Option<Rate>,
+}
+
+impl Interface {
+    /// A placeholder for an interface that is expected but hasn't reported yet.
+    fn expected(interface_type: &'static str) -> Interface {
+        let now = Instant::now();
+        Interface {
+            interface_type,
+            state: State::Unknown,
+            value: String::new(),
+            stamp: None,
+            updated: now,
+            first_seen: now,
+            changed: now,
+            substates: BTreeMap::new(),
+            message: None,
+            tags: Vec::new(),
+            raw: None,
+            reported: false,
+            arrivals: None,
+            expected_rate: None,
+        }
+    }
+
+    /// How the rate the interface reports at compares to the expected one,
+    /// `None` without an expected rate or while it can't tell yet.
+    pub fn pace(&self, now: Instant) -> Option<Pace> {
+        self.expected_rate?.pace(self.arrivals.as_ref()?, now)
+    }
+
+    /// The reported value as a number, for gauge-like interfaces.
+    pub fn number(&self) -> Option<f64> {
+        parse_number(&self.value)
+    }
+
+    /// The worst of the interface's own state and all of its substates.
+    pub fn health(&self) -> Health {
+        self.substates
+            .values()
+            .map(|value| Health::of_substate(value))
+            .fold(self.state.health(), Health::max)
+    }
+
+    /// How bad the interface is by the configured ranking of the states, for
+    /// picking the worst of several. A substate counts as the state of its
+    /// health, so an active interface with a failed part is as bad as an error.
+    pub fn severity(&self, order: &SeverityOrder) -> usize {
+        self.substates
+            .values()
+            .filter_map(|value| match Health::of_substate(value) {
+                Health::Error => Some(State::Error),
+                Health::Warn => Some(State::Warning),
+                Health::Unknown => Some(State::Unknown),
+                Health::Ok | Health::Idle => None,
+            })
+            .map(|state| order.rank(state))
+            .fold(order.rank(self.state), usize::max)
+    }
+}
+
+/// A change of the reported state of an interface, or its first appearance.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    // Position among all transitions ever recorded, lets consumers pick up new ones.
+    pub seq: u64,
+    pub at: SystemTime,
+    pub interface_type: &'static str,
+    pub name: String,
+    pub from: Option<String>,
+    pub to: String,
+    // What `to` was classified as.
+    pub state: State,
+}
+
+/// How an interface compares to the expected ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    // Ordered as the report lists them, the problems first.
+    Missing,
+    NotActive,
+    Unexpected,
+    Up,
+}
+
+/// One line of the report against the expected interfaces.
+#[derive(Debug, Clone)]
+pub struct Check<'a> {
+    pub verdict: Verdict,
+    pub interface_type: &'static str,
+    pub name: &'a String,
+    // The reported state, empty for a missing interface.
+    pub value: &'a str,
+}
+
+/// Running totals over the whole run, for the `--exit-summary`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stats {
+    // Messages received, whether they could be parsed or not.
+    pub messages: u64,
+    pub parse_errors: u64,
+    // Messages the configured transform failed on.
+    pub transform_errors: u64,
+    // Messages that came in another encoding than `--encoding`.
+    pub encoding_mismatches: u64,
+    // Interfaces that reported for the first time, evicted ones count again if they return.
+    pub interfaces_seen: u64,
+    // Interfaces currently in error, and the most there ever were at once.
+    pub errors: usize,
+    pub max_errors: usize,
+    // Changes left out of the transitions by `[[limits.decimate]]`.
+    pub decimated: u64,
+}
+
+/// The changes of a decimated interface since the last one recorded.
+#[derive(Debug, Clone, Copy, Default)]
+struct Decimated {
+    left_out: u64,
+    recorded: Option<Instant>,
+}
+
+// How many of the latest transitions are kept.
+const MAX_TRANSITIONS: usize = 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct App {
+    pub server_states: BTreeMap<String, Interface>,
+    pub publisher_states: BTreeMap<String, Interface>,
+    pub subscriber_states: BTreeMap<String, Interface>,
+    // Bumped on every applied update, lets the UI skip redraws when nothing changed.
+    pub generation: u64,
+    name_filter: NameFilter,
+    state_values: StateValues,
+    limits: Limits,
+    // The names of the interfaces given with `--expected` by their type, as
+    // the same name may be expected under two types.
+    expected: BTreeMap<&'static str, BTreeSet<String>>,
+    // Interfaces evicted or dropped because their type was full, and when that last happened.
+    pub evictions: u64,
+    pub last_eviction: Option<Instant>,
+    // Set once the node has completed its first spin and updates can flow.
+    pub spinning: bool,
+    // Publishers advertising the monitored topic, as last seen in the ROS graph.
+    pub publisher_count: Option<usize>,
+    // Why the subscription to the topic failed, after which nothing arrives anymore.
+    pub subscription_error: Option<String>,
+    // The encoding the last message in a wrong one came in.
+    pub mismatched_encoding: Option<Encoding>,
+    // When the messages of the topic arrived, as of the last one.
+    pub arrivals: Option<Arrivals>,
+    pub transitions: VecDeque<Transition>,
+    transition_count: u64,
+    // By name, the interfaces `[[limits.decimate]]` applies to.
+    decimated: BTreeMap<String, Decimated>,
+    pub stats: Stats,
+}
+
+impl App {
+    pub fn new(name_filter: NameFilter, state_values: StateValues, limits: Limits) -> App {
+        App {
+            server_states: BTreeMap::new(),
+            publisher_states: BTreeMap::new(),
+            subscriber_states: BTreeMap::new(),
+            generation: 0,
+            name_filter,
+            state_values,
+            limits,
+            expected: BTreeMap::new(),
+            evictions: 0,
+            last_eviction: None,
+            spinning: false,
+            publisher_count: None,
+            subscription_error: None,
+            mismatched_encoding: None,
+            arrivals: None,
+            transitions: VecDeque::new(),
+            transition_count: 0,
+            decimated: BTreeMap::new(),
+            stats: Stats::default(),
+        }
+    }
+
+    /// The interface columns in display order, with their titles.
+    pub fn columns(&self) -> [(&'static str, &BTreeMap<String, Interface>); 3] {
+        [
+            ("Server", &self.server_states),
+            ("Publisher", &self.publisher_states),
+            ("Subscriber", &self.subscriber_states),
+        ]
+    }
+
+    pub fn mark_spinning(&mut self) {
+        if !self.spinning {
+            self.spinning = true;
+            self.generation += 1;
+        }
+    }
+
+    pub fn set_publisher_count(&mut self, count: usize) {
+        if self.publisher_count != Some(count) {
+            self.publisher_count = Some(count);
+            self.generation += 1;
+        }
+    }
+
+    pub fn set_subscription_error(&mut self, error: String) {
+        self.subscription_error = Some(error);
+        self.generation += 1;
+    }
+
+    /// After subscribing again worked, shown at once like the error was.
+    pub fn clear_subscription_error(&mut self) {
+        if self.subscription_error.take().is_some() {
+            self.generation += 1;
+        }
+    }
+
+    /// Takes over the state values and limits of a reloaded config. Interfaces
+    /// are classified again as if their states just arrived, except those
+    /// whose state doesn't come from the state values, like watched services.
+    pub fn reconfigure(&mut self, state_values: StateValues, limits: Limits) {
+        let old = std::mem::replace(&mut self.state_values, state_values);
+        self.limits = limits;
+        let mut errors = 0;
+        for states in [
+            &mut self.server_states,
+            &mut self.publisher_states,
+            &mut self.subscriber_states,
+        ] {
+            for (name, interface) in states.iter_mut() {
+                if interface.reported && interface.state == old.classify(name, &interface.value) {
+                    interface.state = self.state_values.classify(name, &interface.value);
+                }
+                interface.expected_rate = self
+                    .state_values
+                    .expected_rate(interface.interface_type, name);
+                errors += (interface.health() == Health::Error) as usize;
+            }
+        }
+        self.stats.errors = errors;
+        self.stats.max_errors = self.stats.max_errors.max(errors);
+        self.generation += 1;
+    }
+
+    /// Forgets every interface and what was seen of the topic, for a new topic.
+    /// Transitions keep counting on, so that consumers of them carry on too.
+    pub fn clear(&mut self) {
+        self.server_states.clear();
+        self.publisher_states.clear();
+        self.subscriber_states.clear();
+        self.transitions.clear();
+        self.decimated.clear();
+        self.publisher_count = None;
+        self.subscription_error = None;
+        self.mismatched_encoding = None;
+        self.arrivals = None;
+        self.stats.errors = 0;
+        self.generation += 1;
+    }
+
+    /// The interface with this name, whatever its type.
+    pub fn interface(&self, name: &str) -> Option<&Interface> {
+        self.columns()
+            .into_iter()
+            .find_map(|(_, states)| states.get(name))
+    }
+
+    /// The interface of that name among those of `interface_type` only.
+    pub fn interface_of(&self, interface_type: &str, name: &str) -> Option<&Interface> {
+        self.columns()
+            .into_iter()
+            .flat_map(|(_, states)| states.get(name))
+            .find(|interface| interface.interface_type == interface_type)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns().iter().all(|(_, states)| states.is_empty())
+    }
+
+    /// Shows an interface that is expected to exist before it reports, so that
+    /// a missing one stands out. Names rejected by the name filter are skipped.
+    pub fn expect(&mut self, interface_type: &str, name: &str) {
+        if !self.name_filter.accepts(name) {
+            return;
+        }
+        let (interface_type, states) = match interface_type {
+            "server" => ("server", &mut self.server_states),
+            "publisher" => ("publisher", &mut self.publisher_states),
+            "subscriber" => ("subscriber", &mut self.subscriber_states),
+            _ => return,
+        };
+        if !states.contains_key(name) {
+            states.insert(name.to_string(), Interface::expected(interface_type));
+            self.generation += 1;
+        }
+        self.expected
+            .entry(interface_type)
+            .or_default()
+            .insert(name.to_string());
+    }
+
+    /// Checks every expected interface for being present and active, and
+    /// every other one for having appeared unexpectedly, problems first.
+    /// Empty when no interfaces are expected.
+    pub fn check_expected(&self) -> Vec<Check<'_>> {
+        if self.expected.is_empty() {
+            return Vec::new();
+        }
+        let mut checks = Vec::new();
+        let expected = self.expected.iter().flat_map(|(&interface_type, names)| {
+            names.iter().map(move |name| (interface_type, name))
+        });
+        for (interface_type, name) in expected {
+            let interface = self
+                .interface_of(interface_type, name)
+                .filter(|interface| interface.reported);
+            let verdict = match interface {
+                None => Verdict::Missing,
+                Some(interface) if interface.health() <= Health::Idle => Verdict::Up,
+                Some(_) => Verdict::NotActive,
+            };
+            checks.push(Check {
+                verdict,
+                interface_type,
+                name,
+                value: interface.map_or("", |interface| interface.value.as_str()),
+            });
+        }
+        for (_, states) in self.columns() {
+            for (name, interface) in states {
+                let expected = self.expected.get(interface.interface_type);
+                if !expected.is_some_and(|names| names.contains(name)) {
+                    checks.push(Check {
+                        verdict: Verdict::Unexpected,
+                        interface_type: interface.interface_type,
+                        name,
+                        value: &interface.value,
+                    });
+                }
+            }
+        }
+        checks.sort_by_key(|check| (check.verdict, check.interface_type, check.name));
+        checks
+    }
+
+    /// How many transitions only the oldest part of the run saw, no longer kept.
+    pub fn dropped_transitions(&self) -> u64 {
+        self.transition_count - self.transitions.len() as u64
+    }
+
+    /// The recorded transitions that come after the one numbered `seq`.
+    pub fn transitions_after(&self, seq: Option<u64>) -> impl Iterator<Item = &Transition> {
+        self.transitions
+            .iter()
+            .filter(move |t| seq.is_none_or(|seq| t.seq > seq))
+    }
+
+    fn record_transition(
+        &mut self,
+        interface_type: &'static str,
+        name: String,
+        from: Option<String>,
+        to: String,
+        state: State,
+    ) {
+        if self.transitions.len() == MAX_TRANSITIONS {
+            self.transitions.pop_front();
+        }
+        self.transitions.push_back(Transition {
+            seq: self.transition_count,
+            at: SystemTime::now(),
+            interface_type,
+            name,
+            from,
+            to,
+            state,
+        });
+        self.transition_count += 1;
+    }
+
+    /// Applies an update, resolving conflicting updates for the same interface
+    /// with last-write-wins: if both the stored and the incoming state carry a
+    /// `stamp`, the one with the later stamp is kept (ties go to the incoming
+    /// one), otherwise the update that arrived last wins.
+    ///
+    /// A sparse update without an interface type changes the interface of
+    /// that name, keeping its message, substates and tags unless it sends
+    /// them. Without such an interface it is a parse error.
+    pub fn update_state(mut self, mut interface: InterfaceState) -> App {
+        if interface.interface_type.is_empty() {
+            let Some(known) = self.interface(&interface.name) else {
+                r2r::log_debug!(
+                    "monitor",
+                    "Dropped a state, no interface_type and no interface '{}' to take it from.",
+                    interface.name
+                );
+                self.stats.parse_errors += 1;
+                return self;
+            };
+            interface.interface_type = known.interface_type.to_string();
+            if interface.message.is_none() {
+                interface.message = known.message.clone();
+            }
+            if interface.substates.is_none() && !known.substates.is_empty() {
+                interface.substates = Some(known.substates.clone());
+            }
+            if interface.tags.is_none() && !known.tags.is_empty() {
+                interface.tags = Some(known.tags.clone());
+            }
+        }
+        let state = self
+            .state_values
+            .classify(&interface.name, &interface.state);
+        self.apply(interface, state)
+    }
+
+    /// Shows whether something is in the ROS graph as an interface,
+    /// independent of the state vocabulary.
+    pub fn update_availability(self, presence: Presence) -> App {
+        let (value, state) = if presence.available {
+            ("available", State::Active)
+        } else if self.state_values.is_idle(&presence.name) {
+            ("unavailable", State::Idle)
+        } else {
+            ("unavailable", State::Inactive)
+        };
+        let interface = InterfaceState {
+            name: presence.name,
+            interface_type: presence.interface_type.to_string(),
+            state: value.to_string(),
+            stamp: None,
+            substates: None,
+            message: Some(presence.message),
+            tags: None,
+            raw: None,
+        };
+        self.apply(interface, state)
+    }
+
+    fn apply(self, interface: InterfaceState, state: State) -> App {
+        if !self.name_filter.accepts(&interface.name) {
+            return self;
+        }
+
+        let name = interface.name;
+        let now = Instant::now();
+        let mut new_interface = Interface {
+            interface_type: "",
+            state,
+            value: interface.state,
+            stamp: interface.stamp,
+            updated: now,
+            first_seen: now,
+            changed: now,
+            substates: interface.substates.unwrap_or_default(),
+            message: interface.message,
+            tags: interface.tags.unwrap_or_default(),
+            raw: interface.raw,
+            reported: true,
+            arrivals: Some(Arrivals::new(now)),
+            expected_rate: None,
+        };
+        let mut new_app = self;
+        new_app.generation += 1;
+        let (interface_type, states) = match interface.interface_type.as_str() {
+            "server" => ("server", &mut new_app.server_states),
+            "publisher" => ("publisher", &mut new_app.publisher_states),
+            "subscriber" => ("subscriber", &mut new_app.subscriber_states),
+            _ => return new_app,
+        };
+        new_interface.interface_type = interface_type;
+        new_interface.expected_rate = new_app.state_values.expected_rate(interface_type, &name);
+        let (previous, previous_state) = states
+            .get(&name)
+            .filter(|i| i.reported)
+            .map(|i| (i.value.clone(), i.state))
+            .unzip();
+        let is_error = |i: &Interface| i.health() == Health::Error;
+        let mut errors =
+            new_app.stats.errors - states.get(&name).map_or(0, |i| is_error(i) as usize);
+        if !states.contains_key(&name) && states.len() >= new_app.limits.max_interfaces_per_type {
+            new_app.evictions += 1;
+            new_app.last_eviction = Some(now);
+            let oldest = states
+                .iter()
+                .min_by_key(|(_, i)| i.updated)
+                .map(|(n, _)| n.clone());
+            match (new_app.limits.eviction, oldest) {
+                (Eviction::LeastRecentlyUpdated, Some(oldest)) => {
+                    if states.remove(&oldest).is_some_and(|i| is_error(&i)) {
+                        errors -= 1;
+                    }
+                    new_app.decimated.remove(&oldest);
+                }
+                _ => return new_app,
+            }
+        }
+        *states = App::update_specific_state(std::mem::take(states), &name, new_interface);
+        let current = &states[&name];
+        new_app.stats.errors = errors + is_error(current) as usize;
+        new_app.stats.max_errors = new_app.stats.max_errors.max(new_app.stats.errors);
+        let (current, state) = (current.value.clone(), current.state);
+        if previous.is_none() {
+            new_app.stats.interfaces_seen += 1;
+        }
+        if previous.as_ref() == Some(&current) {
+            return new_app;
+        }
+        if previous_state == Some(state) && new_app.decimate(&name, now) {
+            new_app.stats.decimated += 1;
+        } else {
+            new_app.record_transition(interface_type, name, previous, current, state);
+        }
+        new_app
+    }
+
+    /// Whether a change of an interface is left out of the transitions by
+    /// the first `[[limits.decimate]]` rule matching its name.
+    fn decimate(&mut self, name: &str, now: Instant) -> bool {
+        let Some(rule) = self
+            .limits
+            .decimate
+            .iter()
+            .find(|rule| rule.name.is_match(name))
+        else {
+            return false;
+        };
+        let decimated = self.decimated.entry(name.to_string()).or_default();
+        let record = match (rule.every, rule.interval_seconds) {
+            (Some(every), _) => decimated.left_out + 1 >= every,
+            (None, Some(seconds)) => decimated.recorded.is_none_or(|recorded| {
+                now.saturating_duration_since(recorded).as_secs_f64() >= seconds
+            }),
+            (None, None) => true,
+        };
+        if record {
+            *decimated = Decimated {
+                left_out: 0,
+                recorded: Some(now),
+            };
+        } else {
+            decimated.left_out += 1;
+        }
+        !record
+    }
+
+    fn update_specific_state(
+        mut states: BTreeMap<String, Interface>,
+        name: &str,
+        mut interface: Interface,
+    ) -> BTreeMap<String, Interface> {
+        if let Some(existing) = states.get(name) {
+            if let (Some(old), Some(new)) = (existing.stamp, interface.stamp) {
+                if new < old {
+                    return states; // Stale update, a newer state is already stored
+                }
+            }
+            if existing.reported {
+                interface.first_seen = existing.first_seen;
+                if let Some(mut arrivals) = existing.arrivals {
+                    arrivals.record(interface.updated);
+                    interface.arrivals = Some(arrivals);
+                }
+                if existing.value == interface.value {
+                    interface.changed = existing.changed;
+                }
+            }
+        }
+        states.insert(name.to_string(), interface);
+        states
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        App::new(
+            NameFilter::default(),
+            StateValues::default(),
+            Limits::default(),
+        )
+    }
+
+    fn stamped(state: &str, stamp: Option<f64>) -> InterfaceState {
+        InterfaceState {
+            name: "/a".to_string(),
+            interface_type: "server".to_string(),
+            state: state.to_string(),
+            stamp,
+            substates: None,
+            message: None,
+            tags: None,
+            raw: None,
+        }
+    }
+
+    fn value(app: &App) -> &str {
+        &app.interface("/a").unwrap().value
+    }
+
+    #[test]
+    fn older_stamp_after_newer_is_ignored() {
+        let app = app()
+            .update_state(stamped("Active", Some(2.0)))
+            .update_state(stamped("Inactive", Some(1.0)));
+        assert_eq!(value(&app), "Active");
+        assert_eq!(app.interface("/a").unwrap().stamp, Some(2.0));
+    }
+
+    #[test]
+    fn equal_stamps_go_to_the_incoming_state() {
+        let app = app()
+            .update_state(stamped("Active", Some(1.0)))
+            .update_state(stamped("Inactive", Some(1.0)));
+        assert_eq!(value(&app), "Inactive");
+    }
+
+    #[test]
+    fn missing_stamp_goes_by_arrival() {
+        let app = app()
+            .update_state(stamped("Active", Some(5.0)))
+            .update_state(stamped("Inactive", None));
+        assert_eq!(value(&app), "Inactive");
+        let app = app
+            .update_state(stamped("Active", Some(1.0)))
+            .update_state(stamped("Inactive", None));
+        assert_eq!(value(&app), "Inactive");
+    }
+
+    fn parsed(json: &str) -> Result<InterfaceState, String> {
+        let mut parsed = None;
+        InterfaceState::parse_each(&mut serde_json::Deserializer::from_str(json), |state| {
+            parsed = Some(state)
+        })
+        .unwrap();
+        parsed.unwrap()
+    }
+
+    #[test]
+    fn empty_interface_type_is_rejected() {
+        let error = parsed(r#"{"name": "/a", "interface_type": " ", "state": "Active"}"#);
+        assert_eq!(error.unwrap_err(), "empty field 'interface_type'");
+    }
+
+    #[test]
+    fn missing_interface_type_updates_a_known_interface() {
+        let sparse = || parsed(r#"{"name": "/a", "state": "Inactive"}"#).unwrap();
+        assert_eq!(sparse().interface_type, "");
+        let app = app().update_state(sparse());
+        assert!(app.interface("/a").is_none());
+        assert_eq!(app.stats.parse_errors, 1);
+
+        let app = app
+            .update_state(stamped("Active", None))
+            .update_state(sparse());
+        assert_eq!(value(&app), "Inactive");
+        assert_eq!(app.interface("/a").unwrap().interface_type, "server");
+    }
+
+    #[test]
+    fn severity_ranks_a_mix_of_states_and_substates() {
+        let state_values: StateValues = toml::from_str(
+            r#"active = ["Active"]
+inactive = ["Inactive"]
+warning = ["Warning"]
+error = ["Error"]
+idle = ["/idle"]"#,
+        )
+        .unwrap();
+        let mut app = App::new(NameFilter::default(), state_values, Limits::default());
+        for (name, state, substate) in [
+            ("/error", "Error", None),
+            ("/warning", "Warning", None),
+            ("/unknown", "Weird", None),
+            ("/ok", "Active", None),
+            ("/idle", "Inactive", None),
+            ("/inactive", "Inactive", None),
+            ("/failed_part", "Active", Some("error")),
+            ("/degraded_part", "Active", Some("degraded")),
+            ("/healthy_part", "Active", Some("ok")),
+        ] {
+            app = app.update_state(InterfaceState {
+                name: name.to_string(),
+                substates: substate
+                    .map(|value| BTreeMap::from([("part".to_string(), value.to_string())])),
+                ..stamped(state, None)
+            });
+        }
+        // The default order and one ranking inactive worst.
+        let custom: SeverityOrder =
+            toml::from_str(r#"order = ["inactive", "error", "warning", "unknown"]"#).unwrap();
+        let inactive = app.interface("/inactive").unwrap().severity(&custom);
+        assert!(inactive > app.interface("/error").unwrap().severity(&custom));
+        for order in [SeverityOrder::default(), custom] {
+            let severity = |name| app.interface(name).unwrap().severity(&order);
+            assert!(severity("/error") > severity("/warning"), "{:?}", order);
+            assert!(severity("/warning") > severity("/unknown"), "{:?}", order);
+            assert!(severity("/unknown") > severity("/ok"), "{:?}", order);
+            assert!(severity("/unknown") > severity("/idle"), "{:?}", order);
+            assert_eq!(severity("/failed_part"), severity("/error"));
+            assert_eq!(severity("/degraded_part"), severity("/warning"));
+            assert_eq!(severity("/healthy_part"), severity("/ok"));
+        }
+    }
+
+    #[test]
+    fn clearing_the_subscription_error_is_a_change() {
+        let mut app = app();
+        app.set_subscription_error("gone".to_string());
+        let generation = app.generation;
+        app.clear_subscription_error();
+        assert!(app.subscription_error.is_none());
+        assert!(app.generation > generation);
+    }
+
+    fn verdicts(app: &App) -> Vec<(Verdict, &str, &str)> {
+        app.check_expected()
+            .into_iter()
+            .map(|check| (check.verdict, check.interface_type, check.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn expected_interfaces_are_checked_by_type_and_name() {
+        let mut app = app();
+        app.expect("server", "/a");
+        let app = app.update_state(InterfaceState {
+            interface_type: "publisher".to_string(),
+            ..stamped("Active", None)
+        });
+        assert_eq!(
+            verdicts(&app),
+            [
+                (Verdict::Missing, "server", "/a"),
+                (Verdict::Unexpected, "publisher", "/a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn the_same_name_may_be_expected_under_two_types() {
+        let mut app = app();
+        app.expect("server", "/a");
+        app.expect("publisher", "/a");
+        let app = app.update_state(stamped("Active", None));
+        assert_eq!(
+            verdicts(&app),
+            [
+                (Verdict::Missing, "publisher", "/a"),
+                (Verdict::Up, "server", "/a"),
+            ]
+        );
+    }
+}
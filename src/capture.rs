@@ -0,0 +1,213 @@
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::app::{App, Interface, Transition};
+use crate::error::MonitorError;
+
+// Bumped whenever a field of the report changes meaning or goes away, new
+// fields may be added without it.
+pub const VERSION: u32 = 1;
+
+/// The report of a `--capture` run over the `window` since `started`, as
+/// JSON. Times are seconds, points in time since the unix epoch:
+///
+/// ```text
+/// {
+///   "version": 1,
+///   "started": 1700000000.0,
+///   "duration_seconds": 30.0,
+///   "topics": [{
+///     "topic": "/monitored_state",
+///     "stats": { "messages", "parse_errors", "transform_errors",
+///                "encoding_mismatches", "interfaces_seen", "max_errors",
+///                "decimated" },
+///     "dropped_transitions": 0,
+///     "interfaces": [{
+///       "name", "interface_type", "state", "classified", "health",
+///       "reported", "message", "substates", "tags",
+///       "transitions", "seconds_in_state": { "<state>": 12.5 }
+///     }],
+///     "transitions": [{ "at", "interface_type", "name", "from", "to" }]
+///   }]
+/// }
+/// ```
+///
+/// `classified` is what the state counts as, e.g. "active" or "error", and
+/// `health` is one of "ok", "idle", "unknown", "warn" and "error". Only as
+/// many transitions as the history keeps are listed, `dropped_transitions`
+/// tells how many older ones are missing from the transitions and from the
+/// time in each state.
+pub fn report(
+    topics: &[String],
+    shared_apps: &[Arc<RwLock<App>>],
+    started: SystemTime,
+    window: Duration,
+) -> Value {
+    let end = started + window;
+    let topics: Vec<Value> = topics
+        .iter()
+        .zip(shared_apps)
+        .map(|(topic, shared_app)| {
+            let app = shared_app.read().unwrap();
+            // By type and name, a server and a publisher may share a name.
+            let mut by_interface: BTreeMap<(&str, &str), Vec<&Transition>> = BTreeMap::new();
+            for transition in &app.transitions {
+                by_interface
+                    .entry((transition.interface_type, &transition.name))
+                    .or_default()
+                    .push(transition);
+            }
+            let interfaces: Vec<Value> = app
+                .columns()
+                .into_iter()
+                .flat_map(|(_, interfaces)| interfaces)
+                .map(|(name, interface)| {
+                    let transitions = by_interface
+                        .get(&(interface.interface_type, name.as_str()))
+                        .map_or(&[][..], Vec::as_slice);
+                    interface_report(name, interface, transitions, end)
+                })
+                .collect();
+            let stats = app.stats;
+            json!({
+                "topic": topic,
+                "stats": {
+                    "messages": stats.messages,
+                    "parse_errors": stats.parse_errors,
+                    "transform_errors": stats.transform_errors,
+                    "encoding_mismatches": stats.encoding_mismatches,
+                    "interfaces_seen": stats.interfaces_seen,
+                    "max_errors": stats.max_errors,
+                    "decimated": stats.decimated,
+                },
+                "dropped_transitions": app.dropped_transitions(),
+                "interfaces": interfaces,
+                "transitions": app.transitions.iter().map(transition_report).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    json!({
+        "version": VERSION,
+        "started": seconds_since_epoch(started),
+        "duration_seconds": window.as_secs_f64(),
+        "topics": topics,
+    })
+}
+
+fn interface_report(
+    name: &str,
+    interface: &Interface,
+    transitions: &[&Transition],
+    end: SystemTime,
+) -> Value {
+    // Each state held from its transition to the next one, the last until the end.
+    let mut seconds_in_state: BTreeMap<&str, f64> = BTreeMap::new();
+    for (i, transition) in transitions.iter().enumerate() {
+        let until = transitions.get(i + 1).map_or(end, |next| next.at);
+        let held = until.duration_since(transition.at).unwrap_or_default();
+        *seconds_in_state.entry(&transition.to).or_default() += held.as_secs_f64();
+    }
+    json!({
+        "name": name,
+        "interface_type": interface.interface_type,
+        "state": interface.value,
+        "classified": format!("{:?}", interface.state).to_lowercase(),
+        "health": format!("{:?}", interface.health()).to_lowercase(),
+        "reported": interface.reported,
+        "message": interface.message,
+        "substates": interface.substates,
+        "tags": interface.tags,
+        "transitions": transitions.len(),
+        "seconds_in_state": seconds_in_state,
+    })
+}
+
+fn transition_report(transition: &Transition) -> Value {
+    json!({
+        "at": seconds_since_epoch(transition.at),
+        "interface_type": transition.interface_type,
+        "name": transition.name,
+        "from": transition.from,
+        "to": transition.to,
+    })
+}
+
+fn seconds_since_epoch(at: SystemTime) -> f64 {
+    at.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Writes the report to `out`, or to stdout without it.
+pub fn write(report: &Value, out: Option<&Path>) -> Result<(), MonitorError> {
+    let text = serde_json::to_string_pretty(report)?;
+    match out {
+        Some(path) => File::create(path)
+            .and_then(|mut file| writeln!(file, "{}", text))
+            .map_err(|source| MonitorError::Write {
+                what: "report",
+                path: path.to_path_buf(),
+                source,
+            }),
+        None => {
+            let _ = writeln!(io::stdout().lock(), "{}", text);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::InterfaceState;
+    use crate::config::{Limits, StateValues};
+    use crate::name_filter::NameFilter;
+
+    fn state(interface_type: &str, state: &str) -> InterfaceState {
+        InterfaceState {
+            name: "/a".to_string(),
+            interface_type: interface_type.to_string(),
+            state: state.to_string(),
+            stamp: None,
+            substates: None,
+            message: None,
+            tags: None,
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn transitions_go_to_the_interface_of_their_type() {
+        let app = App::new(
+            NameFilter::default(),
+            StateValues::default(),
+            Limits::default(),
+        )
+        .update_state(state("server", "Active"))
+        .update_state(state("server", "Inactive"))
+        .update_state(state("server", "Active"))
+        .update_state(state("publisher", "Active"));
+        let shared_apps = [Arc::new(RwLock::new(app))];
+        let report = report(
+            &["/states".to_string()],
+            &shared_apps,
+            SystemTime::now(),
+            Duration::from_secs(1),
+        );
+        let interfaces = report["topics"][0]["interfaces"].as_array().unwrap();
+        let transitions = |interface_type: &str| {
+            interfaces
+                .iter()
+                .find(|interface| interface["interface_type"] == interface_type)
+                .unwrap()["transitions"]
+                .clone()
+        };
+        assert_eq!(transitions("server"), 3);
+        assert_eq!(transitions("publisher"), 1);
+    }
+}
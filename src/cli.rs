@@ -0,0 +1,311 @@
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::encoding::Encoding;
+use crate::zone::Zone;
+
+/// Terminal monitor for interface states published on a ROS topic.
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// TOML configuration file.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// YAML file listing the interfaces that should exist, and optionally the topic.
+    #[arg(long, value_name = "FILE")]
+    pub expected: Option<PathBuf>,
+
+    /// Only monitor interfaces whose name matches this glob (repeatable).
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Never monitor interfaces whose name matches this glob (repeatable), wins over --include.
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Show whether this service is available in the ROS graph (repeatable).
+    #[arg(long, value_name = "NAME")]
+    pub watch_service: Vec<String>,
+
+    /// Show whether this action is available in the ROS graph (repeatable).
+    #[arg(long, value_name = "NAME")]
+    pub watch_action: Vec<String>,
+
+    /// Monitor everything in the ROS graph instead of a state topic: topics,
+    /// by whether they have publishers, services and actions.
+    #[arg(long, conflicts_with_all = ["compare", "demo"])]
+    pub from_graph: bool,
+
+    /// Make columns from what this regex captures from the names, e.g. '/(\w+)/',
+    /// instead of by interface type.
+    #[arg(long, value_name = "REGEX")]
+    pub group_by: Option<String>,
+
+    /// Within each column, list the interfaces under a header row per distinct
+    /// part of the names this regex captures, e.g. '/(robot\d+)/'. Enter on a
+    /// header collapses its group.
+    #[arg(long, value_name = "REGEX")]
+    pub subgroup_by: Option<String>,
+
+    /// Monitor two topics side by side, e.g. the old and the new topic of a
+    /// migrated component, marking interfaces whose states differ.
+    #[arg(long, num_args = 2, value_names = ["TOPIC_A", "TOPIC_B"], conflicts_with = "log_mode")]
+    pub compare: Vec<String>,
+
+    /// Start with interface names replaced by stable aliases, for sharing screenshots.
+    #[arg(long)]
+    pub redact: bool,
+
+    /// Show a fixed set of made-up interfaces instead of monitoring ROS, for
+    /// screenshots and trying out the UI.
+    #[arg(long, conflicts_with_all = ["compare", "log_mode"])]
+    pub demo: bool,
+
+    /// Freeze the view when an interface goes to Error, until it is resumed.
+    #[arg(long)]
+    pub pause_on_error: bool,
+
+    /// Start with every column shown as a grid of colored cells instead of a
+    /// list, to fit fleets of many alike interfaces on screen.
+    #[arg(long)]
+    pub grid: bool,
+
+    /// Start with the active interfaces of every column collapsed into a
+    /// count line, listing only the others, Enter on the line expands it.
+    #[arg(long)]
+    pub collapse_active: bool,
+
+    /// Start with the interfaces of every type in a single list, for searching
+    /// across types and for narrow terminals.
+    #[arg(long)]
+    pub unified: bool,
+
+    /// Keep selecting the interface that changed last, for wall displays.
+    /// Navigating pauses it until the keys are left alone for a while.
+    #[arg(long)]
+    pub follow: bool,
+
+    /// Grow the footer to list every key binding at all times, for learning
+    /// the keys or for screens without a keyboard at hand.
+    #[arg(long)]
+    pub expanded_help: bool,
+
+    /// Render inline instead of on the alternate screen, keeping the last frame on exit.
+    #[arg(long)]
+    pub no_alt_screen: bool,
+
+    /// The most frames drawn per second, a burst of updates or keys within
+    /// a frame is drawn once.
+    #[arg(long, value_name = "FPS", default_value_t = 30, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_fps: u32,
+
+    /// On exit, print a one-line JSON summary of the run to stderr.
+    #[arg(long)]
+    pub exit_summary: bool,
+
+    /// Run for this long, e.g. 30s, 5m or 1h, then write a JSON report of
+    /// every interface and transition seen and exit. With --log-mode it runs
+    /// headless, for CI.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, conflicts_with = "demo")]
+    pub capture: Option<Duration>,
+
+    /// Check the config and files given, create the ROS node, subscribe and
+    /// wait this long, 5s if not given, for messages, then print what would
+    /// be monitored and exit without the TUI. Exits nonzero on any problem,
+    /// for deployment scripts.
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, num_args = 0..=1, default_missing_value = "5s", conflicts_with_all = ["demo", "from_graph", "capture"])]
+    pub dry_run: Option<Duration>,
+
+    /// Where --capture writes its report, stdout if not given.
+    #[arg(long, value_name = "FILE", requires = "capture")]
+    pub out: Option<PathBuf>,
+
+    /// Answer `GET /healthz` on this port with 200 while data arrives and every
+    /// expected interface is active, 503 otherwise.
+    #[cfg(feature = "health")]
+    #[arg(long, value_name = "PORT", conflicts_with = "demo")]
+    pub health_port: Option<u16>,
+
+    /// Also publish the monitored states as a diagnostic_msgs/DiagnosticArray
+    /// on this topic, for rqt_robot_monitor and diagnostic aggregators.
+    #[arg(long, value_name = "TOPIC", conflicts_with = "demo")]
+    pub diagnostics_topic: Option<String>,
+
+    /// Also serve the states on the session or system D-Bus, as
+    /// org.sequenceplanner.TuiStateMonitor with a Snapshot method and a
+    /// Transition signal.
+    #[cfg(feature = "dbus")]
+    #[arg(long, value_enum, value_name = "BUS", conflicts_with = "demo")]
+    pub dbus: Option<Bus>,
+
+    /// Also read JSON states from the MQTT broker at this host or host:port,
+    /// tagged "mqtt", next to the ROS topic.
+    #[cfg(feature = "mqtt")]
+    #[arg(long, value_name = "BROKER", requires = "mqtt_topic", conflicts_with_all = ["compare", "demo"])]
+    pub mqtt: Option<String>,
+
+    /// The MQTT topic the states are published on.
+    #[cfg(feature = "mqtt")]
+    #[arg(long, value_name = "TOPIC", requires = "mqtt")]
+    pub mqtt_topic: Option<String>,
+
+    /// How the states are encoded: json in std_msgs/String messages, msgpack or
+    /// cbor in std_msgs/UInt8MultiArray ones.
+    #[arg(long, value_enum, default_value_t = Encoding::Json)]
+    pub encoding: Encoding,
+
+    /// Print a line for every state transition instead of showing the TUI, for running headless.
+    #[arg(long)]
+    pub log_mode: bool,
+
+    /// How --log-mode prints the transitions: text, or a JSON object per line
+    /// for piping into jq or alerting scripts. Repeated identical states print
+    /// nothing either way.
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = LogFormat::Text, requires = "log_mode")]
+    pub log_format: LogFormat,
+
+    /// In log mode, also append every line to this file, kept across runs.
+    #[arg(long, value_name = "FILE", requires = "log_mode")]
+    pub record: Option<PathBuf>,
+
+    /// In log mode, first print the last N lines of the --record file.
+    #[arg(long, value_name = "N", requires = "record")]
+    pub tail: Option<usize>,
+
+    /// Time zone of the times shown: local, utc, or an offset such as +02:00.
+    #[arg(long, value_name = "TZ", default_value = "local", value_parser = Zone::parse)]
+    pub tz: Zone,
+
+    /// Draw only ASCII, for serial consoles and fonts without box drawing.
+    /// Also the default when LANG, LC_CTYPE or LC_ALL isn't a UTF-8 locale.
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Draw in the colors of a base16 scheme file, e.g. the one of the
+    /// terminal's theme. Slots it doesn't set keep the default colors.
+    #[arg(long, value_name = "FILE")]
+    pub base16: Option<PathBuf>,
+
+    /// Whether to use colors, auto detects support from TERM and NO_COLOR,
+    /// and with --log-mode also colors only when stdout is a terminal.
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorMode::Auto)]
+    pub color: ColorMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+#[cfg(feature = "dbus")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Bus {
+    Session,
+    System,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Parses a duration in seconds, or in the unit it ends with: s, m or h.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let (number, unit) = match text.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => text.split_at(i),
+        None => (text, "s"),
+    };
+    let seconds = match unit {
+        "s" => 1.0,
+        "m" => 60.0,
+        "h" => 3600.0,
+        _ => return Err(format!("unknown unit '{}', expected s, m or h", unit)),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a duration like 30s, 5m or 1h", text))?;
+    Duration::try_from_secs_f64(number * seconds).map_err(|e| format!("'{}': {}", text, e))
+}
+
+impl Cli {
+    /// Parses the command line, with `--version` also telling what ROS
+    /// environment the monitor was built for and runs in.
+    pub fn parse_args() -> Cli {
+        let matches = Cli::command().long_version(long_version()).get_matches();
+        Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
+}
+
+fn long_version() -> String {
+    let sourced = std::env::var("ROS_DISTRO").unwrap_or_else(|_| "none sourced".to_string());
+    let rmw = std::env::var("RMW_IMPLEMENTATION").unwrap_or_else(|_| "default".to_string());
+    format!(
+        "{}\nr2r {}, built for ROS {}\nenvironment: ROS {}, RMW {}",
+        env!("CARGO_PKG_VERSION"),
+        env!("R2R_VERSION"),
+        r2r::ROS_DISTRO,
+        sourced,
+        rmw
+    )
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Publish random interface states, for demos and load-testing the monitor.
+    PublishTest(PublishTestArgs),
+    /// Time a flood of made-up states through the update path, without ROS or
+    /// the UI, for tracking performance on the target hardware.
+    SelfBench(SelfBenchArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct PublishTestArgs {
+    /// Messages published per second.
+    #[arg(long, default_value_t = 5.0)]
+    pub rate: f64,
+
+    /// Topic to publish on.
+    #[arg(long, default_value = "/monitored_state")]
+    pub topic: String,
+
+    /// Number of interfaces of each type to randomize among.
+    #[arg(long, default_value_t = 10)]
+    pub interfaces: usize,
+
+    /// States to randomize among.
+    #[arg(long, value_delimiter = ',', default_value = "Active,Inactive")]
+    pub states: Vec<String>,
+
+    /// Seed of the randomization, to publish the same sequence again. A
+    /// random one is picked and printed if not given.
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct SelfBenchArgs {
+    /// Number of states applied.
+    #[arg(long, default_value_t = 1_000_000)]
+    pub updates: usize,
+
+    /// Number of interfaces of each type to randomize among.
+    #[arg(long, default_value_t = 1000)]
+    pub interfaces: usize,
+
+    /// States to randomize among.
+    #[arg(long, value_delimiter = ',', default_value = "Active,Inactive")]
+    pub states: Vec<String>,
+
+    /// Seed of the randomization, to apply the same states again. A random
+    /// one is picked and printed if not given.
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
@@ -0,0 +1,29 @@
+#[cfg(feature = "clipboard")]
+use std::sync::Mutex;
+
+#[cfg(feature = "clipboard")]
+use arboard::Clipboard;
+
+// On X11 and Wayland the copied text is served by whoever copied it, so the
+// clipboard is kept open for as long as the monitor runs.
+#[cfg(feature = "clipboard")]
+static CLIPBOARD: Mutex<Option<Clipboard>> = Mutex::new(None);
+
+/// Puts text on the system clipboard.
+#[cfg(feature = "clipboard")]
+pub fn copy(text: String) -> Result<(), String> {
+    let mut clipboard = CLIPBOARD.lock().unwrap();
+    if clipboard.is_none() {
+        *clipboard = Some(Clipboard::new().map_err(|e| e.to_string())?);
+    }
+    clipboard
+        .as_mut()
+        .unwrap()
+        .set_text(text)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: String) -> Result<(), String> {
+    Err("built without the clipboard feature".to_string())
+}
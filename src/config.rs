@@ -0,0 +1,615 @@
+use globset::{Glob, GlobMatcher};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use r2r::QosProfile;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::app::{parse_number, Health, State};
+use crate::error::MonitorError;
+use crate::keymap::Keys;
+use crate::link::Rate;
+
+pub const DEFAULT_TOPIC: &str = "/monitored_state";
+
+/// Settings read from the `--config` TOML file. Every section is optional.
+///
+/// `${VAR}` and `${VAR:-default}` are expanded in the topic names only, the
+/// `topic` and the keys of `topic_qos`, which differ between deployments,
+/// e.g. by a robot's namespace. Elsewhere, like in the jq of `transform` or
+/// in the state values, the text is taken as written.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // The topic to monitor.
+    pub topic: String,
+    pub states: StateValues,
+    pub limits: Limits,
+    pub ui: UiConfig,
+    pub banner: Banner,
+    pub severity: SeverityOrder,
+    // Keys by action name, e.g. `quit = "x"` or `down = ["n", "down"]`.
+    pub keys: BTreeMap<String, Keys>,
+    // A jq expression reshaping every message into the expected states.
+    pub transform: Option<String>,
+    // How every topic is subscribed to, and single topics where their
+    // publishers need something else, by topic.
+    pub qos: QosSettings,
+    pub topic_qos: BTreeMap<String, QosSettings>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            topic: DEFAULT_TOPIC.to_string(),
+            states: StateValues::default(),
+            limits: Limits::default(),
+            ui: UiConfig::default(),
+            banner: Banner::default(),
+            severity: SeverityOrder::default(),
+            keys: BTreeMap::new(),
+            transform: None,
+            qos: QosSettings::default(),
+            topic_qos: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config, MonitorError> {
+        let text = std::fs::read_to_string(path).map_err(|source| MonitorError::Read {
+            what: "config",
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let invalid = |reason: String| MonitorError::Invalid {
+            what: "config",
+            path: path.to_path_buf(),
+            reason,
+        };
+        let mut config: Config = toml::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+        config.topic = expand_env(&config.topic).map_err(|e| invalid(format!("topic: {}", e)))?;
+        config.topic_qos = std::mem::take(&mut config.topic_qos)
+            .into_iter()
+            .map(|(topic, qos)| {
+                expand_env(&topic)
+                    .map(|topic| (topic, qos))
+                    .map_err(|e| invalid(format!("topic_qos '{}': {}", topic, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        config
+            .qos
+            .validate()
+            .map_err(|e| invalid(format!("qos: {}", e)))?;
+        for (topic, qos) in &config.topic_qos {
+            qos.validate()
+                .map_err(|e| invalid(format!("topic_qos '{}': {}", topic, e)))?;
+        }
+        for (i, rate) in config.states.rates.iter().enumerate() {
+            rate.validate()
+                .map_err(|e| invalid(format!("states.rates[{}]: {}", i, e)))?;
+        }
+        for (i, rule) in config.limits.decimate.iter().enumerate() {
+            rule.validate()
+                .map_err(|e| invalid(format!("limits.decimate[{}]: {}", i, e)))?;
+        }
+        Ok(config)
+    }
+
+    /// The QoS to subscribe to a topic with: what the topic sets, then what
+    /// `[qos]` sets, then the ROS defaults.
+    pub fn qos(&self, topic: &str) -> QosProfile {
+        let own = self.topic_qos.get(topic);
+        let mut qos = QosProfile::default();
+        qos = match setting(own, &self.qos, |qos| qos.reliability) {
+            Some(Reliability::Reliable) => qos.reliable(),
+            Some(Reliability::BestEffort) => qos.best_effort(),
+            None => qos,
+        };
+        qos = match setting(own, &self.qos, |qos| qos.durability) {
+            Some(Durability::Volatile) => qos.volatile(),
+            Some(Durability::TransientLocal) => qos.transient_local(),
+            None => qos,
+        };
+        let history = setting(own, &self.qos, |qos| qos.history);
+        match (history, setting(own, &self.qos, |qos| qos.depth)) {
+            (Some(History::KeepAll), _) => qos.keep_all(),
+            (_, Some(depth)) => qos.keep_last(depth),
+            _ => qos,
+        }
+    }
+}
+
+/// A QoS policy as a topic sets it, or else as `[qos]` does.
+fn setting<T>(
+    own: Option<&QosSettings>,
+    all: &QosSettings,
+    policy: fn(&QosSettings) -> Option<T>,
+) -> Option<T> {
+    own.and_then(policy).or_else(|| policy(all))
+}
+
+/// QoS policies of a subscription, each left to what it falls back to when unset.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QosSettings {
+    pub reliability: Option<Reliability>,
+    pub durability: Option<Durability>,
+    pub history: Option<History>,
+    // How many messages are queued with keep-last history.
+    pub depth: Option<usize>,
+}
+
+impl QosSettings {
+    fn validate(&self) -> Result<(), String> {
+        match (self.history, self.depth) {
+            (_, Some(0)) => Err("depth must be at least 1".to_string()),
+            (Some(History::KeepAll), Some(_)) => {
+                Err("depth only applies to keep-last history".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Reliability {
+    Reliable,
+    BestEffort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Durability {
+    Volatile,
+    TransientLocal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum History {
+    KeepLast,
+    KeepAll,
+}
+
+/// Tells whenever the config file changes, for as long as the watcher lives.
+/// Its directory is watched, since editors often replace the file on save.
+pub fn watch(path: &Path) -> notify::Result<(RecommendedWatcher, UnboundedReceiver<()>)> {
+    let (changes, receiver) = tokio::sync::mpsc::unbounded_channel();
+    let name = path.file_name().map(|name| name.to_os_string());
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if !event.kind.is_access() && event.paths.iter().any(|p| p.file_name() == name.as_deref()) {
+            let _ = changes.send(());
+        }
+    })?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    Ok((watcher, receiver))
+}
+
+/// Replaces `${VAR}` with the value of the environment variable `VAR`, or with
+/// `default` for `${VAR:-default}` when it is unset. An unset variable without
+/// a default is an error.
+fn expand_env(value: &str) -> Result<String, String> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| format!("unterminated '${{' in '{}'", value))?;
+        let reference = &rest[start + 2..start + end];
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+        match (std::env::var(name), default) {
+            (Ok(var), _) => expanded.push_str(&var),
+            (Err(_), Some(default)) => expanded.push_str(default),
+            (Err(_), None) => return Err(format!("environment variable '{}' is not set", name)),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Which reported state values mean what, compared case-insensitively.
+/// A numeric value is classified by the thresholds for its interface if
+/// there are any, by the lists otherwise, e.g. `active = ["1"]`. A value in
+/// none of the lists is `Unknown`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateValues {
+    pub active: Vec<String>,
+    pub inactive: Vec<String>,
+    pub warning: Vec<String>,
+    pub error: Vec<String>,
+    // Interfaces whose inactive state is healthy, e.g. ones started on demand,
+    // by globs of their names.
+    #[serde(deserialize_with = "globs")]
+    pub idle: Vec<GlobMatcher>,
+    pub thresholds: Vec<Thresholds>,
+    // The first rule matching an interface tells how often it should report.
+    pub rates: Vec<ExpectedRate>,
+}
+
+/// How often the interfaces whose name matches a glob, all of them if not
+/// given, and of a type if given, should report, e.g. a control loop at
+/// 100 Hz. Reporting slower or faster by more than `tolerance`, a fraction
+/// of `hz`, is flagged even while the state is active.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedRate {
+    #[serde(default = "any_name", deserialize_with = "glob")]
+    pub name: GlobMatcher,
+    pub interface_type: Option<String>,
+    pub hz: f64,
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64,
+}
+
+fn any_name() -> GlobMatcher {
+    Glob::new("*").unwrap().compile_matcher()
+}
+
+fn default_tolerance() -> f64 {
+    0.2
+}
+
+impl ExpectedRate {
+    fn validate(&self) -> Result<(), String> {
+        if !(self.hz.is_finite() && self.hz > 0.0) {
+            return Err("hz must be positive".to_string());
+        }
+        if !(0.0..1.0).contains(&self.tolerance) {
+            return Err("tolerance must be at least 0 and below 1".to_string());
+        }
+        match self.interface_type.as_deref() {
+            None | Some("server" | "publisher" | "subscriber") => Ok(()),
+            Some(other) => Err(format!(
+                "unknown interface_type '{}', expected server, publisher or subscriber",
+                other
+            )),
+        }
+    }
+
+    pub fn rate(&self) -> Rate {
+        Rate {
+            hz: self.hz,
+            tolerance: self.tolerance,
+        }
+    }
+}
+
+/// Ranges of a numeric state, e.g. a battery percentage or a latency, for
+/// the interfaces whose name matches a glob. A value past an error bound is
+/// `Error`, past a warning bound `Warning`, otherwise it is `Active`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Thresholds {
+    #[serde(deserialize_with = "glob")]
+    pub name: GlobMatcher,
+    pub warn_above: Option<f64>,
+    pub error_above: Option<f64>,
+    pub warn_below: Option<f64>,
+    pub error_below: Option<f64>,
+}
+
+impl Thresholds {
+    fn classify(&self, value: f64) -> State {
+        let above = |bound: Option<f64>| bound.is_some_and(|bound| value > bound);
+        let below = |bound: Option<f64>| bound.is_some_and(|bound| value < bound);
+        if above(self.error_above) || below(self.error_below) {
+            State::Error
+        } else if above(self.warn_above) || below(self.warn_below) {
+            State::Warning
+        } else {
+            State::Active
+        }
+    }
+}
+
+fn glob<'de, D: Deserializer<'de>>(deserializer: D) -> Result<GlobMatcher, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Glob::new(&pattern)
+        .map(|glob| glob.compile_matcher())
+        .map_err(serde::de::Error::custom)
+}
+
+fn globs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<GlobMatcher>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| Glob::new(pattern).map(|glob| glob.compile_matcher()))
+        .collect::<Result<_, _>>()
+        .map_err(serde::de::Error::custom)
+}
+
+impl Default for StateValues {
+    fn default() -> Self {
+        StateValues {
+            active: vec!["Active".to_string()],
+            inactive: vec!["Inactive".to_string()],
+            warning: Vec::new(),
+            error: Vec::new(),
+            idle: Vec::new(),
+            rates: Vec::new(),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+/// How the interactive monitor looks.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    // Seconds an interface stays highlighted after its state changed, 0 turns it off.
+    pub flash_seconds: f64,
+    pub density: Density,
+    // Seconds without an update after which an interface counts as stale in
+    // the totals, 0 never counts one.
+    pub stale_seconds: f64,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            flash_seconds: 0.0,
+            density: Density::default(),
+            stale_seconds: 60.0,
+        }
+    }
+}
+
+/// How much room an interface takes in the lists, toggled with a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+    // A line per interface, to fit as many as possible.
+    #[default]
+    Compact,
+    // The age and the message on a second line, and the table rows spaced out.
+    Comfortable,
+}
+
+impl Density {
+    /// The lines of an interface's row.
+    pub fn lines(self) -> u16 {
+        match self {
+            Density::Compact => 1,
+            Density::Comfortable => 2,
+        }
+    }
+
+    pub fn toggled(self) -> Density {
+        match self {
+            Density::Compact => Density::Comfortable,
+            Density::Comfortable => Density::Compact,
+        }
+    }
+}
+
+/// How many interfaces in a state make the totals line call the system
+/// degraded or critical, since systems tolerate different amounts of partial
+/// failure. A threshold of 0 is never reached.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Banner {
+    pub warn_if_inactive: usize,
+    pub warn_if_warnings: usize,
+    pub critical_if_inactive: usize,
+    pub critical_if_errors: usize,
+}
+
+impl Default for Banner {
+    fn default() -> Self {
+        Banner {
+            warn_if_inactive: 1,
+            warn_if_warnings: 1,
+            critical_if_inactive: 0,
+            critical_if_errors: 1,
+        }
+    }
+}
+
+impl Banner {
+    /// `Ok`, `Warn` for degraded or `Error` for critical.
+    pub fn level(&self, inactive: usize, warnings: usize, errors: usize) -> Health {
+        let reached = |count: usize, threshold: usize| threshold > 0 && count >= threshold;
+        if reached(errors, self.critical_if_errors) || reached(inactive, self.critical_if_inactive)
+        {
+            Health::Error
+        } else if reached(inactive, self.warn_if_inactive)
+            || reached(warnings, self.warn_if_warnings)
+        {
+            Health::Warn
+        } else {
+            Health::Ok
+        }
+    }
+}
+
+/// Which states are worse than which, worst first, for the colors showing
+/// the worst of several interfaces and for sorting by state. Some rank an
+/// inactive interface worse than one in error. States left out rank below
+/// every listed one.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeverityOrder {
+    pub order: Vec<State>,
+}
+
+impl Default for SeverityOrder {
+    fn default() -> Self {
+        SeverityOrder {
+            order: vec![
+                State::Error,
+                State::Inactive,
+                State::Warning,
+                State::Unknown,
+                State::Idle,
+                State::Active,
+            ],
+        }
+    }
+}
+
+impl SeverityOrder {
+    /// Higher for worse states, 0 for those that aren't listed.
+    pub fn rank(&self, state: State) -> usize {
+        self.order
+            .iter()
+            .position(|listed| *listed == state)
+            .map_or(0, |i| self.order.len() - i)
+    }
+}
+
+/// Bounds on what is tracked, so that a publisher inventing a new name for
+/// every message can't exhaust memory.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limits {
+    pub max_interfaces_per_type: usize,
+    pub eviction: Eviction,
+    // The first rule whose glob matches an interface applies to it.
+    pub decimate: Vec<Decimation>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_interfaces_per_type: 10_000,
+            eviction: Eviction::LeastRecentlyUpdated,
+            decimate: Vec::new(),
+        }
+    }
+}
+
+/// Records only some of the changes of the interfaces whose name matches a
+/// glob, e.g. of a reading published at 1 kHz: every `every`th, or at most
+/// one per `interval_seconds`. The state shown is always the latest, and a
+/// change of what the state counts as is always recorded.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Decimation {
+    #[serde(deserialize_with = "glob")]
+    pub name: GlobMatcher,
+    pub every: Option<u64>,
+    pub interval_seconds: Option<f64>,
+}
+
+impl Decimation {
+    fn validate(&self) -> Result<(), String> {
+        match (self.every, self.interval_seconds) {
+            (Some(0), _) => Err("every must be at least 1".to_string()),
+            (_, Some(seconds)) if !(seconds.is_finite() && seconds > 0.0) => {
+                Err("interval_seconds must be positive".to_string())
+            }
+            (Some(_), Some(_)) => Err("set either every or interval_seconds".to_string()),
+            (None, None) => Err("set every or interval_seconds".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// What happens to an interface with a new name once its type is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Eviction {
+    // Make room by forgetting the interface that was updated longest ago.
+    LeastRecentlyUpdated,
+    // Keep what is tracked and ignore the new interface.
+    DropNew,
+}
+
+impl StateValues {
+    /// Whether an interface being inactive is healthy.
+    pub fn is_idle(&self, name: &str) -> bool {
+        self.idle.iter().any(|idle| idle.is_match(name))
+    }
+
+    /// How often an interface should report, if a rule tells.
+    pub fn expected_rate(&self, interface_type: &str, name: &str) -> Option<Rate> {
+        self.rates
+            .iter()
+            .find(|rate| {
+                rate.interface_type
+                    .as_deref()
+                    .is_none_or(|t| t == interface_type)
+                    && rate.name.is_match(name)
+            })
+            .map(ExpectedRate::rate)
+    }
+
+    pub fn classify(&self, name: &str, value: &str) -> State {
+        if let Some(number) = parse_number(value) {
+            let thresholds = self.thresholds.iter().find(|t| t.name.is_match(name));
+            if let Some(thresholds) = thresholds {
+                return thresholds.classify(number);
+            }
+        }
+        let is_in = |values: &[String]| values.iter().any(|v| v.eq_ignore_ascii_case(value));
+        if is_in(&self.active) {
+            State::Active
+        } else if is_in(&self.error) {
+            State::Error
+        } else if is_in(&self.warning) {
+            State::Warning
+        } else if is_in(&self.inactive) && self.is_idle(name) {
+            State::Idle
+        } else if is_in(&self.inactive) {
+            State::Inactive
+        } else {
+            State::Unknown
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_values(toml: &str) -> StateValues {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn numbers_without_thresholds_go_by_the_lists() {
+        let values = state_values(
+            r#"active = ["1"]
+inactive = ["0"]"#,
+        );
+        assert_eq!(values.classify("/a", "1"), State::Active);
+        assert_eq!(values.classify("/a", "0"), State::Inactive);
+        assert_eq!(values.classify("/a", "2"), State::Unknown);
+    }
+
+    #[test]
+    fn stale_seconds_default_to_a_minute() {
+        let ui: UiConfig = toml::from_str("").unwrap();
+        assert_eq!(ui.stale_seconds, 60.0);
+        let ui: UiConfig = toml::from_str("stale_seconds = 5").unwrap();
+        assert_eq!(ui.stale_seconds, 5.0);
+    }
+
+    #[test]
+    fn thresholds_for_the_name_win_over_the_lists() {
+        let values = state_values(
+            r#"active = ["1"]
+
+[[thresholds]]
+name = "/battery*"
+error_below = 5.0"#,
+        );
+        assert_eq!(values.classify("/battery", "1"), State::Error);
+        assert_eq!(values.classify("/other", "1"), State::Active);
+    }
+}
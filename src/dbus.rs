@@ -0,0 +1,122 @@
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use zbus::{connection, interface, SignalContext};
+
+use crate::app::{App, Transition};
+use crate::capture;
+use crate::cli::Bus;
+
+// The well-known name taken on the bus, and where the monitor is served.
+const BUS_NAME: &str = "org.sequenceplanner.TuiStateMonitor";
+const OBJECT_PATH: &str = "/org/sequenceplanner/TuiStateMonitor";
+
+// How often new transitions are picked up from the apps to be signalled.
+const POLL_PERIOD: Duration = Duration::from_millis(100);
+
+/// What is monitored, as the `org.sequenceplanner.TuiStateMonitor1`
+/// interface serves it.
+struct Monitor {
+    topics: Arc<RwLock<Vec<String>>>,
+    shared_apps: Vec<Arc<RwLock<App>>>,
+    started: SystemTime,
+}
+
+#[interface(name = "org.sequenceplanner.TuiStateMonitor1")]
+impl Monitor {
+    /// Every monitored interface and the transitions kept, as the JSON a
+    /// `--capture` report holds, covering the run so far.
+    fn snapshot(&self) -> String {
+        let window = SystemTime::now()
+            .duration_since(self.started)
+            .unwrap_or_default();
+        capture::report(
+            &self.topics.read().unwrap(),
+            &self.shared_apps,
+            self.started,
+            window,
+        )
+        .to_string()
+    }
+
+    /// An interface changed state, `from` is empty for a new one and
+    /// `state` is what `to` counts as, e.g. "active" or "error". Sent for
+    /// interfaces muted on the screen too.
+    #[zbus(signal)]
+    async fn transition(
+        context: &SignalContext<'_>,
+        topic: &str,
+        interface_type: &str,
+        name: &str,
+        from: &str,
+        to: &str,
+        state: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Takes `org.sequenceplanner.TuiStateMonitor` on the session or system
+/// bus and serves the monitor at `/org/sequenceplanner/TuiStateMonitor`,
+/// for panel applets and scripts without ROS. Aborting the returned task
+/// leaves the bus.
+pub async fn serve(
+    bus: Bus,
+    topics: Arc<RwLock<Vec<String>>>,
+    shared_apps: Vec<Arc<RwLock<App>>>,
+    started: SystemTime,
+) -> zbus::Result<JoinHandle<()>> {
+    let builder = match bus {
+        Bus::Session => connection::Builder::session()?,
+        Bus::System => connection::Builder::system()?,
+    };
+    let monitor = Monitor {
+        topics: topics.clone(),
+        shared_apps: shared_apps.clone(),
+        started,
+    };
+    let connection = builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, monitor)?
+        .build()
+        .await?;
+    let interface = connection
+        .object_server()
+        .interface::<_, Monitor>(OBJECT_PATH)
+        .await?;
+    Ok(tokio::task::spawn(async move {
+        // Keeps the name and the object served while the task runs.
+        let _connection = connection;
+        let mut last_seqs = vec![None; shared_apps.len()];
+        let mut timer = tokio::time::interval(POLL_PERIOD);
+        loop {
+            timer.tick().await;
+            let mut new: Vec<(String, Transition)> = Vec::new();
+            for ((shared_app, last_seq), topic) in shared_apps
+                .iter()
+                .zip(&mut last_seqs)
+                .zip(topics.read().unwrap().iter())
+            {
+                let app = shared_app.read().unwrap();
+                for transition in app.transitions_after(*last_seq) {
+                    *last_seq = Some(transition.seq);
+                    new.push((topic.clone(), transition.clone()));
+                }
+            }
+            for (topic, transition) in new {
+                let state = format!("{:?}", transition.state).to_lowercase();
+                let sent = Monitor::transition(
+                    interface.signal_context(),
+                    &topic,
+                    transition.interface_type,
+                    &transition.name,
+                    transition.from.as_deref().unwrap_or(""),
+                    &transition.to,
+                    &state,
+                )
+                .await;
+                if let Err(e) = sent {
+                    r2r::log_warn!("monitor", "Could not signal a transition: '{}'.", e);
+                }
+            }
+        }
+    }))
+}
@@ -0,0 +1,131 @@
+use globset::Glob;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::app::{App, InterfaceState};
+use crate::config::{Limits, StateValues};
+use crate::name_filter::NameFilter;
+
+// Fixed, so that every run of `--demo` shows the same interfaces.
+const DEMO_SEED: u64 = 42;
+
+// Robots of the generated fleet, each with one interface of every type.
+const FLEET_SIZE: usize = 8;
+
+fn state(name: &str, interface_type: &str, state: &str) -> InterfaceState {
+    InterfaceState {
+        name: name.to_string(),
+        interface_type: interface_type.to_string(),
+        state: state.to_string(),
+        stamp: None,
+        substates: None,
+        message: None,
+        tags: None,
+        raw: None,
+    }
+}
+
+/// Moves the last update of an interface into the past.
+fn backdate(app: &mut App, name: &str, age: Duration) {
+    let Some(now) = Instant::now().checked_sub(age) else {
+        return;
+    };
+    for states in [
+        &mut app.server_states,
+        &mut app.publisher_states,
+        &mut app.subscriber_states,
+    ] {
+        if let Some(interface) = states.get_mut(name) {
+            interface.updated = now;
+            interface.changed = now;
+            interface.first_seen = interface.first_seen.min(now);
+        }
+    }
+}
+
+/// A fixed, representative set of interfaces in every state, for screenshots
+/// and trying out the UI without ROS: active, inactive, idle, erroring, stale,
+/// flapping, composite and expected-but-missing ones, and a generated fleet.
+pub fn app(name_filter: NameFilter) -> App {
+    let state_values = StateValues {
+        active: vec!["Active".to_string()],
+        inactive: vec!["Inactive".to_string()],
+        warning: vec!["Warning".to_string()],
+        error: vec!["Error".to_string()],
+        idle: vec![Glob::new("/docking/charger").unwrap().compile_matcher()],
+        thresholds: Vec::new(),
+        rates: Vec::new(),
+    };
+    let mut app = App::new(name_filter, state_values, Limits::default());
+    app.expect("server", "/docking/station");
+
+    let mut updates = vec![
+        state("/navigation/planner", "server", "Active"),
+        state("/navigation/map_server", "server", "Active"),
+        state("/camera/front", "publisher", "Warning"),
+        state("/arm/controller", "subscriber", "Inactive"),
+        state("/docking/charger", "server", "Inactive"),
+        state("/battery/monitor", "publisher", "Active"),
+    ];
+    let mut gripper = state("/arm/gripper", "server", "Error");
+    gripper.message = Some("gripper jammed, retrying".to_string());
+    gripper.tags = Some(vec!["critical".to_string(), "arm".to_string()]);
+    updates.push(gripper);
+    let mut base = state("/base/driver", "publisher", "Active");
+    base.substates = Some(BTreeMap::from([
+        ("comm".to_string(), "ok".to_string()),
+        ("motors".to_string(), "ok".to_string()),
+        ("power".to_string(), "warn".to_string()),
+    ]));
+    base.tags = Some(vec!["critical".to_string()]);
+    updates.push(base);
+    // Flaps between states, showing up as a run in the transition log.
+    for i in 0..6 {
+        let value = if i % 2 == 0 { "Active" } else { "Error" };
+        updates.push(state("/lidar/front", "publisher", value));
+    }
+
+    let mut rng = StdRng::seed_from_u64(DEMO_SEED);
+    let mut ages = Vec::new();
+    for robot in 1..=FLEET_SIZE {
+        for interface_type in ["server", "publisher", "subscriber"] {
+            let value = match rng.gen_range(0..10) {
+                0 => "Error",
+                1 => "Warning",
+                2 => "Inactive",
+                _ => "Active",
+            };
+            let name = format!("/fleet/robot{}/{}", robot, interface_type);
+            ages.push((name.clone(), Duration::from_secs(rng.gen_range(0..50))));
+            updates.push(state(&name, interface_type, value));
+        }
+    }
+
+    for update in updates {
+        app = app.update_state(update);
+    }
+    for (name, age) in ages {
+        backdate(&mut app, &name, age);
+    }
+    // Stale: no update for longer than the monitor considers fresh.
+    backdate(&mut app, "/battery/monitor", Duration::from_secs(5 * 60));
+    backdate(
+        &mut app,
+        "/navigation/map_server",
+        Duration::from_secs(2 * 60 * 60),
+    );
+
+    // Spread the transitions over the last minute, oldest first.
+    let count = app.transitions.len() as u32;
+    let now = SystemTime::now();
+    for (i, transition) in app.transitions.iter_mut().enumerate() {
+        let age = Duration::from_secs(60) * (count - i as u32) / count.max(1);
+        transition.at = now.checked_sub(age).unwrap_or(now);
+    }
+
+    app.mark_spinning();
+    app.set_publisher_count(1);
+    app
+}
@@ -0,0 +1,103 @@
+use r2r::builtin_interfaces::msg::Time;
+use r2r::diagnostic_msgs::msg::{DiagnosticArray, DiagnosticStatus, KeyValue};
+use r2r::std_msgs::msg::Header;
+use r2r::QosProfile;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+
+use crate::app::{App, Health, Interface};
+
+// The rate diagnostic aggregators and rqt_robot_monitor expect.
+const PUBLISH_PERIOD: Duration = Duration::from_secs(1);
+
+/// Publishes what is monitored as a `diagnostic_msgs/DiagnosticArray` on
+/// `topic` every second, one status per interface, for the standard ROS
+/// diagnostics tools, until the returned task is aborted.
+pub fn spawn(
+    arc_node: Arc<Mutex<r2r::Node>>,
+    topic: &str,
+    topics: Arc<RwLock<Vec<String>>>,
+    shared_apps: Vec<Arc<RwLock<App>>>,
+) -> Result<JoinHandle<()>, r2r::Error> {
+    let publisher = arc_node
+        .lock()
+        .unwrap()
+        .create_publisher::<DiagnosticArray>(topic, QosProfile::default())?;
+    Ok(tokio::task::spawn(async move {
+        let mut timer = tokio::time::interval(PUBLISH_PERIOD);
+        loop {
+            timer.tick().await;
+            let message = diagnostic_array(&topics.read().unwrap(), &shared_apps);
+            if let Err(e) = publisher.publish(&message) {
+                r2r::log_warn!("monitor", "Could not publish the diagnostics: '{}'.", e);
+            }
+        }
+    }))
+}
+
+/// A status for every interface of every topic, the topic as its hardware id,
+/// muted ones included as on `/healthz`.
+fn diagnostic_array(topics: &[String], shared_apps: &[Arc<RwLock<App>>]) -> DiagnosticArray {
+    let mut status = Vec::new();
+    for (topic, shared_app) in topics.iter().zip(shared_apps) {
+        let app = shared_app.read().unwrap();
+        for (_, interfaces) in app.columns() {
+            status.extend(
+                interfaces
+                    .iter()
+                    .map(|(name, interface)| diagnostic_status(topic, name, interface)),
+            );
+        }
+    }
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    DiagnosticArray {
+        header: Header {
+            stamp: Time {
+                sec: since_epoch.as_secs() as i32,
+                nanosec: since_epoch.subsec_nanos(),
+            },
+            frame_id: String::new(),
+        },
+        status,
+    }
+}
+
+fn diagnostic_status(topic: &str, name: &str, interface: &Interface) -> DiagnosticStatus {
+    // An expected interface that never reported has nothing current to tell.
+    let level = match interface.health() {
+        _ if !interface.reported => DiagnosticStatus::STALE,
+        Health::Ok | Health::Idle => DiagnosticStatus::OK,
+        Health::Unknown | Health::Warn => DiagnosticStatus::WARN,
+        Health::Error => DiagnosticStatus::ERROR,
+    };
+    let mut values = vec![
+        key_value("interface_type", interface.interface_type),
+        key_value("state", &interface.value),
+    ];
+    values.extend(
+        interface
+            .substates
+            .iter()
+            .map(|(key, value)| key_value(key, value)),
+    );
+    DiagnosticStatus {
+        level,
+        name: name.to_string(),
+        message: interface
+            .message
+            .clone()
+            .unwrap_or_else(|| interface.value.clone()),
+        hardware_id: topic.to_string(),
+        values,
+    }
+}
+
+fn key_value(key: &str, value: &str) -> KeyValue {
+    KeyValue {
+        key: key.to_string(),
+        value: value.to_string(),
+    }
+}
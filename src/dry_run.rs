@@ -0,0 +1,153 @@
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::app::{App, Verdict};
+use crate::cli::Cli;
+use crate::config::Config;
+use crate::error::MonitorError;
+use crate::expected::Expected;
+use crate::name_filter::NameFilter;
+use crate::transform::Transform;
+
+/// Checks that the monitor would run as set up, without starting it, for
+/// deployment scripts: the files given were loaded and checked before this
+/// runs, it creates the node, subscribes to every topic and waits up to
+/// `wait` for a message on each. Prints what it would monitor, and fails on
+/// a topic nothing arrived on or whose messages could not be used.
+pub async fn run(
+    cli: &Cli,
+    config: &Config,
+    expected: &Expected,
+    transform: Option<Arc<Transform>>,
+    name_filter: NameFilter,
+    wait: Duration,
+) -> Result<(), MonitorError> {
+    let node = crate::create_node("monitor")?;
+    let arc_node = Arc::new(Mutex::new(node));
+    let topics = if cli.compare.is_empty() {
+        vec![config.topic.clone()]
+    } else {
+        cli.compare.clone()
+    };
+
+    let mut shared_apps = Vec::new();
+    let mut subscriptions = Vec::new();
+    for topic in &topics {
+        let mut app = App::new(
+            name_filter.clone(),
+            config.states.clone(),
+            config.limits.clone(),
+        );
+        for interface in &expected.interfaces {
+            app.expect(interface.interface_type.as_str(), &interface.name);
+        }
+        let shared_app = Arc::new(RwLock::new(app));
+        subscriptions.push(
+            crate::spawn_subscriber(
+                arc_node.clone(),
+                topic,
+                config.qos(topic),
+                cli.encoding,
+                transform.clone(),
+                &shared_app,
+            )
+            .await?,
+        );
+        shared_apps.push(shared_app);
+    }
+
+    // Spun as the monitor spins it, until a message arrived on every topic.
+    let started = Instant::now();
+    let mut spin = tokio::time::interval(crate::SPIN_PERIOD);
+    while started.elapsed() < wait
+        && shared_apps
+            .iter()
+            .any(|app| app.read().unwrap().stats.messages == 0)
+    {
+        spin.tick().await;
+        arc_node.lock().unwrap().spin_once(Duration::ZERO);
+    }
+    for subscription in &subscriptions {
+        subscription.abort();
+    }
+
+    match &cli.config {
+        Some(path) => println!("config: {}", path.display()),
+        None => println!("config: none, the defaults"),
+    }
+    if let Some(path) = &cli.expected {
+        println!(
+            "expected: {} interfaces from {}",
+            expected.interfaces.len(),
+            path.display()
+        );
+    }
+    if !cli.include.is_empty() || !cli.exclude.is_empty() {
+        println!(
+            "names: including {}, excluding {}",
+            or_none(&cli.include),
+            or_none(&cli.exclude)
+        );
+    }
+    let mut failures = Vec::new();
+    let node = arc_node.lock().unwrap();
+    for (topic, shared_app) in topics.iter().zip(&shared_apps) {
+        let app = shared_app.read().unwrap();
+        let publishers = node
+            .get_publishers_info_by_topic(topic, false)
+            .map_or_else(|_| "unknown".to_string(), |info| info.len().to_string());
+        let stats = app.stats;
+        println!(
+            "topic '{}' as {}: {} publishers, {} messages, {} interfaces, {} parse errors",
+            topic,
+            cli.encoding.name(),
+            publishers,
+            stats.messages,
+            stats.interfaces_seen,
+            stats.parse_errors + stats.transform_errors
+        );
+        let checks = app.check_expected();
+        let missing: Vec<&str> = checks
+            .iter()
+            .filter(|check| check.verdict == Verdict::Missing)
+            .map(|check| check.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            println!("  not seen yet: {}", missing.join(", "));
+        }
+
+        if let Some(other) = app.mismatched_encoding {
+            failures.push(format!(
+                "the messages on '{}' are {}, not {}",
+                topic,
+                other.name(),
+                cli.encoding.name()
+            ));
+        } else if stats.messages == 0 {
+            failures.push(format!(
+                "no message arrived on '{}' within {}s",
+                topic,
+                wait.as_secs_f64()
+            ));
+        } else if stats.interfaces_seen == 0 && stats.parse_errors + stats.transform_errors > 0 {
+            failures.push(format!(
+                "none of the messages on '{}' could be parsed",
+                topic
+            ));
+        }
+    }
+    if failures.is_empty() {
+        println!("ok");
+        Ok(())
+    } else {
+        Err(MonitorError::DryRun(failures.join(", ")))
+    }
+}
+
+fn or_none(globs: &[String]) -> String {
+    if globs.is_empty() {
+        "none".to_string()
+    } else {
+        globs.join(" ")
+    }
+}
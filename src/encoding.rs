@@ -0,0 +1,153 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::app::InterfaceState;
+
+/// How the states are encoded in the messages. JSON comes as a string
+/// message, the binary encodings as byte arrays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    #[default]
+    Json,
+    Msgpack,
+    Cbor,
+}
+
+const ENCODINGS: [Encoding; 3] = [Encoding::Json, Encoding::Msgpack, Encoding::Cbor];
+
+/// Why a message could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    Invalid(String),
+    // The message decodes in this other encoding, `--encoding` is likely wrong.
+    Mismatch(Encoding),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecodeError::Invalid(e) => f.write_str(e),
+            DecodeError::Mismatch(other) => write!(f, "the message is {}", other.name()),
+        }
+    }
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Json => "JSON",
+            Encoding::Msgpack => "MessagePack",
+            Encoding::Cbor => "CBOR",
+        }
+    }
+
+    /// Parses the states of a message one at a time, see `InterfaceState::parse_each`.
+    pub fn parse_each(
+        self,
+        data: &[u8],
+        each: impl FnMut(Result<InterfaceState, String>),
+    ) -> Result<(), DecodeError> {
+        let result = match self {
+            Encoding::Json => {
+                let mut deserializer = serde_json::Deserializer::from_slice(data);
+                InterfaceState::parse_each(&mut deserializer, each)
+                    .and_then(|()| deserializer.end())
+                    .map_err(|e| e.to_string())
+            }
+            Encoding::Msgpack => {
+                InterfaceState::parse_each(&mut rmp_serde::Deserializer::new(data), each)
+                    .map_err(|e| e.to_string())
+            }
+            // ciborium has no deserializer of its own to stream from, so a CBOR
+            // batch is decoded as a whole first.
+            Encoding::Cbor => self.decode_all(data).and_then(|value| {
+                InterfaceState::parse_each(value, each).map_err(|e| e.to_string())
+            }),
+        };
+        result.map_err(|e| self.error(data, e))
+    }
+
+    /// Decodes a whole message, for the transform to reshape.
+    pub fn decode(self, data: &[u8]) -> Result<serde_json::Value, DecodeError> {
+        self.decode_all(data).map_err(|e| self.error(data, e))
+    }
+
+    /// Decodes a message that has to end with the decoded value.
+    fn decode_all(self, data: &[u8]) -> Result<serde_json::Value, String> {
+        let mut rest = data;
+        let value = match self {
+            Encoding::Json => return serde_json::from_slice(data).map_err(|e| e.to_string()),
+            Encoding::Msgpack => {
+                serde_json::Value::deserialize(&mut rmp_serde::Deserializer::new(&mut rest))
+                    .map_err(|e| e.to_string())?
+            }
+            Encoding::Cbor => ciborium::de::from_reader(&mut rest).map_err(|e| e.to_string())?,
+        };
+        if !rest.is_empty() {
+            return Err(format!("{} bytes after the message", rest.len()));
+        }
+        Ok(value)
+    }
+
+    /// Tells a message in another encoding apart from garbage: one that
+    /// decodes as a whole into states, objects or arrays, in another encoding.
+    fn error(self, data: &[u8], e: String) -> DecodeError {
+        let other = ENCODINGS
+            .into_iter()
+            .filter(|&other| other != self)
+            .find(|other| {
+                other
+                    .decode_all(data)
+                    .is_ok_and(|value| value.is_object() || value.is_array())
+            });
+        match other {
+            Some(other) => DecodeError::Mismatch(other),
+            None => DecodeError::Invalid(format!("not valid {}: {}", self.name(), e)),
+        }
+    }
+
+    /// A message as it is logged: JSON as text, the binary encodings in hex.
+    pub fn display(self, data: &[u8]) -> String {
+        match self {
+            Encoding::Json => String::from_utf8_lossy(data).into_owned(),
+            Encoding::Msgpack | Encoding::Cbor => {
+                data.iter().map(|byte| format!("{:02x}", byte)).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cbor(value: serde_json::Value) -> Vec<u8> {
+        let mut data = Vec::new();
+        ciborium::ser::into_writer(&value, &mut data).unwrap();
+        data
+    }
+
+    fn states(encoding: Encoding, data: &[u8]) -> Result<Vec<String>, DecodeError> {
+        let mut names = Vec::new();
+        encoding.parse_each(data, |state| names.push(state.unwrap().name))?;
+        Ok(names)
+    }
+
+    #[test]
+    fn cbor_read_as_json_is_a_mismatch() {
+        let data = cbor(serde_json::json!([
+            {"name": "/a", "interface_type": "server", "state": "Active"}
+        ]));
+        assert_eq!(states(Encoding::Cbor, &data), Ok(vec!["/a".to_string()]));
+        assert_eq!(
+            states(Encoding::Json, &data),
+            Err(DecodeError::Mismatch(Encoding::Cbor))
+        );
+    }
+
+    #[test]
+    fn garbage_is_invalid_rather_than_a_mismatch() {
+        let error = states(Encoding::Json, b"\xff\x00 not a message").unwrap_err();
+        assert!(matches!(error, DecodeError::Invalid(_)), "{:?}", error);
+    }
+}
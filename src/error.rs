@@ -0,0 +1,93 @@
+use std::io;
+use std::path::PathBuf;
+
+// Why creating a node usually fails, the most common problem on a first run.
+const ROS_HINT: &str = "is your ROS environment sourced and ROS_DOMAIN_ID set?";
+
+/// What can go wrong in the monitor, by what failed, so that a caller can
+/// tell a missing ROS environment from a bad topic or an invalid file.
+#[derive(Debug, thiserror::Error)]
+pub enum MonitorError {
+    /// The ROS context or node could not be created, `what` names which.
+    #[error("failed to create ROS {what} ({source}) — {}", ROS_HINT)]
+    RosSetup {
+        what: String,
+        #[source]
+        source: r2r::Error,
+    },
+    /// Any other ROS call, like creating a timer.
+    #[error("ROS call failed: {0}")]
+    Ros(#[from] r2r::Error),
+    #[error("could not subscribe to '{topic}': {source}")]
+    Subscription {
+        topic: String,
+        #[source]
+        source: r2r::Error,
+    },
+    #[error("could not publish on '{topic}': {source}")]
+    Publish {
+        topic: String,
+        #[source]
+        source: r2r::Error,
+    },
+    /// The stream of a subscription ended, nothing more will arrive on it.
+    #[error("the subscription ended")]
+    SubscriptionEnded,
+    /// The merger of an app is gone, what is parsed has nowhere to go.
+    #[error("the merger stopped")]
+    MergerStopped,
+    /// A file given on the command line, `what` tells which, could not be read.
+    #[error("could not read {what} '{}': {source}", .path.display())]
+    Read {
+        what: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("could not write {what} '{}': {source}", .path.display())]
+    Write {
+        what: &'static str,
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// A file was read but what it holds is invalid.
+    #[error("invalid {what} '{}': {reason}", .path.display())]
+    Invalid {
+        what: &'static str,
+        path: PathBuf,
+        reason: String,
+    },
+    /// A part of the config that is only checked once it is applied, like
+    /// the keys or the transform.
+    #[error("invalid config: {0}")]
+    Config(String),
+    /// What `--dry-run` found would keep the monitor from working.
+    #[error("dry run failed: {0}")]
+    DryRun(String),
+    /// A command line argument out of what it allows.
+    #[error("{0}")]
+    Argument(String),
+    #[error("invalid --include/--exclude pattern: {0}")]
+    Glob(#[from] globset::Error),
+    #[error("invalid --group-by/--subgroup-by: {0}")]
+    GroupBy(#[from] regex::Error),
+    #[cfg(feature = "health")]
+    #[error("could not serve health probes on port {port}: {source}")]
+    Health {
+        port: u16,
+        #[source]
+        source: io::Error,
+    },
+    #[cfg(feature = "dbus")]
+    #[error("could not serve on the D-Bus: {0}")]
+    Dbus(#[from] zbus::Error),
+    #[error("no config directory, set HOME or XDG_CONFIG_HOME")]
+    NoConfigDir,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("could not serialize: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("a task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
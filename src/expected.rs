@@ -0,0 +1,75 @@
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::error::MonitorError;
+
+/// The interfaces a system is expected to have, read from the `--expected`
+/// YAML file, optionally with the topic they are published on.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Expected {
+    #[serde(default)]
+    pub topic: Option<String>,
+    #[serde(default)]
+    pub interfaces: Vec<ExpectedInterface>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedInterface {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub interface_type: InterfaceType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterfaceType {
+    Server,
+    Publisher,
+    Subscriber,
+}
+
+impl InterfaceType {
+    /// The `interface_type` that interfaces of this type report.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InterfaceType::Server => "server",
+            InterfaceType::Publisher => "publisher",
+            InterfaceType::Subscriber => "subscriber",
+        }
+    }
+}
+
+impl Expected {
+    pub fn load(path: &Path) -> Result<Expected, MonitorError> {
+        let text = std::fs::read_to_string(path).map_err(|source| MonitorError::Read {
+            what: "expected interfaces",
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let invalid = |reason: String| MonitorError::Invalid {
+            what: "expected interfaces",
+            path: path.to_path_buf(),
+            reason,
+        };
+        let expected: Expected = serde_yaml::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+
+        let mut seen = BTreeSet::new();
+        for (i, interface) in expected.interfaces.iter().enumerate() {
+            if interface.name.is_empty() {
+                return Err(invalid(format!("interfaces[{}]: empty name", i)));
+            }
+            if !seen.insert((interface.interface_type, interface.name.as_str())) {
+                return Err(invalid(format!(
+                    "interfaces[{}]: {} '{}' is listed twice",
+                    i,
+                    interface.interface_type.as_str(),
+                    interface.name
+                )));
+            }
+        }
+        Ok(expected)
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+// Services every node offers for its parameters, noise in a picture of the graph.
+const PARAMETER_SERVICES: [&str; 7] = [
+    "describe_parameters",
+    "get_parameter_types",
+    "get_parameters",
+    "get_type_description",
+    "list_parameters",
+    "set_parameters",
+    "set_parameters_atomically",
+];
+
+/// Whether something is in the ROS graph, shown as an interface.
+#[derive(Debug, Clone)]
+pub struct Presence {
+    pub name: String,
+    pub interface_type: &'static str,
+    pub available: bool,
+    // What it is, or who provides it.
+    pub message: String,
+}
+
+/// Services and actions whose availability in the ROS graph is shown as
+/// interfaces of their own, set with `--watch-service` and `--watch-action`.
+#[derive(Debug, Clone, Default)]
+pub struct Watched {
+    pub services: Vec<String>,
+    pub actions: Vec<String>,
+}
+
+impl Watched {
+    pub fn new(services: &[String], actions: &[String]) -> Watched {
+        Watched {
+            services: services.iter().map(|name| qualify(name)).collect(),
+            actions: actions.iter().map(|name| qualify(name)).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.services.is_empty() && self.actions.is_empty()
+    }
+
+    /// Whether each watched service and action is currently advertised, with
+    /// what it is. An action counts as available once its goal service is.
+    pub fn availability(&self, node: &r2r::Node) -> r2r::Result<Vec<Presence>> {
+        let advertised = node.get_service_names_and_types()?;
+        let services = self.services.iter().map(|name| Presence {
+            name: name.clone(),
+            interface_type: "server",
+            available: advertised.contains_key(name),
+            message: "service".to_string(),
+        });
+        let actions = self.actions.iter().map(|name| Presence {
+            name: name.clone(),
+            interface_type: "server",
+            available: advertised.contains_key(&format!("{}/_action/send_goal", name)),
+            message: "action".to_string(),
+        });
+        Ok(services.chain(actions).collect())
+    }
+}
+
+/// Everything in the ROS graph as interfaces, for `--from-graph`: topics
+/// with publishers as publishers, topics only subscribed to as subscribers,
+/// services and actions as servers. What disappears stays, unavailable.
+#[derive(Debug, Default)]
+pub struct Discovery {
+    // The interface type of everything found so far, by name.
+    seen: BTreeMap<String, &'static str>,
+}
+
+impl Discovery {
+    pub fn poll(&mut self, node: &r2r::Node) -> r2r::Result<Vec<Presence>> {
+        let mut found = Vec::new();
+        for (topic, types) in node.get_topic_names_and_types()? {
+            let publishers = node.get_publishers_info_by_topic(&topic, false)?;
+            let presence = if publishers.is_empty() {
+                Presence {
+                    name: topic,
+                    interface_type: "subscriber",
+                    // Listening, but nothing is published to it.
+                    available: false,
+                    message: format!("{}, no publisher", types.join(", ")),
+                }
+            } else {
+                let nodes: Vec<String> = publishers
+                    .iter()
+                    .map(|info| qualify(&format!("{}/{}", info.node_namespace, info.node_name)))
+                    .collect();
+                Presence {
+                    name: topic,
+                    interface_type: "publisher",
+                    available: true,
+                    message: format!("{} from {}", types.join(", "), nodes.join(", ")),
+                }
+            };
+            found.push(presence);
+        }
+        for (service, types) in node.get_service_names_and_types()? {
+            let presence = if let Some(action) = service.strip_suffix("/_action/send_goal") {
+                Presence {
+                    name: action.to_string(),
+                    interface_type: "server",
+                    available: true,
+                    message: "action".to_string(),
+                }
+            } else if service.contains("/_action/")
+                || service
+                    .rsplit_once('/')
+                    .is_some_and(|(_, name)| PARAMETER_SERVICES.contains(&name))
+            {
+                continue;
+            } else {
+                Presence {
+                    name: service,
+                    interface_type: "server",
+                    available: true,
+                    message: types.join(", "),
+                }
+            };
+            found.push(presence);
+        }
+
+        let mut gone: BTreeMap<String, &'static str> = self.seen.clone();
+        for presence in &found {
+            gone.remove(&presence.name);
+            self.seen
+                .insert(presence.name.clone(), presence.interface_type);
+        }
+        found.extend(gone.into_iter().map(|(name, interface_type)| Presence {
+            name,
+            interface_type,
+            available: false,
+            message: "gone from the graph".to_string(),
+        }));
+        Ok(found)
+    }
+}
+
+// Names in the graph are fully qualified.
+fn qualify(name: &str) -> String {
+    if name.starts_with('/') {
+        name.to_string()
+    } else {
+        format!("/{}", name)
+    }
+}
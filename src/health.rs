@@ -0,0 +1,94 @@
+use std::io;
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::app::{App, Verdict};
+
+// Enough for the request line of a probe, the rest of the request is ignored.
+const REQUEST_BUFFER: usize = 1024;
+
+/// Serves `GET /healthz` on every address at `port`, for liveness and
+/// readiness probes: 200 while every topic is receiving data and every
+/// expected interface is active, 503 with the failures otherwise. Interfaces
+/// muted on the screen still count, muting only acknowledges them there.
+/// Aborting the returned task stops listening.
+pub async fn serve(
+    port: u16,
+    topics: Vec<String>,
+    shared_apps: Vec<Arc<RwLock<App>>>,
+) -> io::Result<JoinHandle<()>> {
+    let listener = TcpListener::bind(("0.0.0.0", port)).await?;
+    Ok(tokio::task::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    let failures = failures(&topics, &shared_apps);
+                    tokio::task::spawn(async move {
+                        if let Err(e) = respond(stream, failures).await {
+                            r2r::log_debug!("monitor", "Could not answer a health probe: '{}'.", e);
+                        }
+                    });
+                }
+                Err(e) => r2r::log_warn!("monitor", "Could not accept a health probe: '{}'.", e),
+            }
+        }
+    }))
+}
+
+/// Why the monitored system isn't healthy, nothing if it is.
+fn failures(topics: &[String], shared_apps: &[Arc<RwLock<App>>]) -> Vec<String> {
+    let mut failures = Vec::new();
+    for (topic, shared_app) in topics.iter().zip(shared_apps) {
+        let app = shared_app.read().unwrap();
+        if let Some(error) = &app.subscription_error {
+            failures.push(format!("{}: subscription failed: {}", topic, error));
+        } else if app.stats.messages == 0 {
+            failures.push(format!("{}: no messages received", topic));
+        }
+        for check in app.check_expected() {
+            match check.verdict {
+                Verdict::Missing => failures.push(format!(
+                    "{}: {} {} is missing",
+                    topic, check.interface_type, check.name
+                )),
+                Verdict::NotActive => failures.push(format!(
+                    "{}: {} {} is {}",
+                    topic, check.interface_type, check.name, check.value
+                )),
+                Verdict::Up | Verdict::Unexpected => {}
+            }
+        }
+    }
+    failures
+}
+
+async fn respond(mut stream: TcpStream, failures: Vec<String>) -> io::Result<()> {
+    let mut buffer = [0; REQUEST_BUFFER];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let mut request_line = request.split_whitespace();
+    let (status, body) = match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/healthz")) if failures.is_empty() => {
+            ("200 OK", serde_json::json!({ "status": "ok" }))
+        }
+        (Some("GET"), Some("/healthz")) => (
+            "503 Service Unavailable",
+            serde_json::json!({ "status": "failing", "failures": failures }),
+        ),
+        _ => (
+            "404 Not Found",
+            serde_json::json!({ "error": "only GET /healthz" }),
+        ),
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
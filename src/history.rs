@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::Widget;
+
+use crate::app::{App, Health, Transition};
+
+/// How many samples are kept, at one per `SAMPLE_PERIOD` this is ten minutes.
+const MAX_SAMPLES: usize = 600;
+
+/// Number of interfaces of one type in each health, at one moment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub ok: usize,
+    pub unknown: usize,
+    pub warn: usize,
+    pub error: usize,
+}
+
+impl Counts {
+    fn total(&self) -> usize {
+        self.ok + self.unknown + self.warn + self.error
+    }
+}
+
+/// Periodic samples of the state distribution of every interface type, oldest first.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    samples: VecDeque<Vec<Counts>>,
+}
+
+impl History {
+    /// Records the current distribution, leaving out muted interfaces.
+    pub fn sample(&mut self, app: &App, muted: &BTreeMap<&'static str, BTreeSet<String>>) {
+        let counts = app
+            .columns()
+            .iter()
+            .map(|(_, states)| {
+                let mut counts = Counts::default();
+                let unmuted = states.iter().filter(|(name, interface)| {
+                    !muted
+                        .get(interface.interface_type)
+                        .is_some_and(|names| names.contains(*name))
+                });
+                for (_, interface) in unmuted {
+                    match interface.health() {
+                        // Idle is how these interfaces are meant to be.
+                        Health::Ok | Health::Idle => counts.ok += 1,
+                        Health::Unknown => counts.unknown += 1,
+                        Health::Warn => counts.warn += 1,
+                        Health::Error => counts.error += 1,
+                    }
+                }
+                counts
+            })
+            .collect();
+        if self.samples.len() == MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(counts);
+    }
+
+    /// The samples of the column at `index`, oldest first.
+    pub fn column(&self, index: usize) -> Vec<Counts> {
+        self.samples
+            .iter()
+            .map(|sample| sample.get(index).copied().unwrap_or_default())
+            .collect()
+    }
+}
+
+/// Splits transitions into runs of consecutive ones of the same interface, so
+/// that a flapping interface can be shown as a single line.
+pub fn runs<'a>(transitions: impl Iterator<Item = &'a Transition>) -> Vec<Vec<&'a Transition>> {
+    let mut runs: Vec<Vec<&Transition>> = Vec::new();
+    for transition in transitions {
+        match runs.last_mut() {
+            Some(run)
+                if run[0].interface_type == transition.interface_type
+                    && run[0].name == transition.name =>
+            {
+                run.push(transition)
+            }
+            _ => runs.push(vec![transition]),
+        }
+    }
+    runs
+}
+
+/// A stacked column chart with one terminal column per sample, the newest at
+/// the right edge. Bars are scaled to the largest total in view.
+pub struct DistributionChart<'a> {
+    pub samples: &'a [Counts],
+    // Without colors each health is drawn with its own symbol instead.
+    pub colors: bool,
+}
+
+impl Widget for DistributionChart<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let shown = &self.samples[self.samples.len().saturating_sub(area.width as usize)..];
+        let max = shown.iter().map(Counts::total).max().unwrap_or(0);
+        if max == 0 {
+            return;
+        }
+        let x0 = area.right() - shown.len() as u16;
+        for (i, counts) in shown.iter().enumerate() {
+            // Worst at the bottom so that problems stay visible in short bars.
+            let segments = [
+                (counts.error, Color::Red, "X"),
+                (counts.warn, Color::Yellow, "!"),
+                (counts.unknown, Color::Gray, "?"),
+                (counts.ok, Color::Green, "="),
+            ];
+            let mut below = 0;
+            let mut y = area.bottom();
+            for (count, color, symbol) in segments {
+                let symbol = if self.colors { "█" } else { symbol };
+                below += count;
+                let top = area.bottom() - (below * area.height as usize).div_ceil(max) as u16;
+                while y > top {
+                    y -= 1;
+                    buf.get_mut(x0 + i as u16, y)
+                        .set_symbol(symbol)
+                        .set_fg(color);
+                }
+            }
+        }
+    }
+}
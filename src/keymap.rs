@@ -0,0 +1,429 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// What a key does in the monitor, outside of editing the filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Filter,
+    Help,
+    NextProblem,
+    PreviousProblem,
+    History,
+    CollapseRuns,
+    Down,
+    Up,
+    Right,
+    Left,
+    MoveRight,
+    MoveLeft,
+    Pin,
+    Mute,
+    MuteProblems,
+    UnmuteAll,
+    Redact,
+    Grid,
+    Unified,
+    Sort,
+    Report,
+    Snapshot,
+    Resume,
+    Palette,
+    ToggleGroup,
+    Follow,
+    Payload,
+    Density,
+    Note,
+    Resubscribe,
+    CollapseActive,
+    Back,
+}
+
+// Every action by its name in the `[keys]` config section, with its default keys.
+const ACTIONS: [(&str, Action, &[KeyCode]); 33] = [
+    ("quit", Action::Quit, &[KeyCode::Char('q')]),
+    ("filter", Action::Filter, &[KeyCode::Char('/')]),
+    ("help", Action::Help, &[KeyCode::Char('?')]),
+    ("next_problem", Action::NextProblem, &[KeyCode::Char('n')]),
+    (
+        "previous_problem",
+        Action::PreviousProblem,
+        &[KeyCode::Char('N')],
+    ),
+    ("history", Action::History, &[KeyCode::Char('H')]),
+    ("collapse_runs", Action::CollapseRuns, &[KeyCode::Char('c')]),
+    ("down", Action::Down, &[KeyCode::Down, KeyCode::Char('j')]),
+    ("up", Action::Up, &[KeyCode::Up, KeyCode::Char('k')]),
+    (
+        "right",
+        Action::Right,
+        &[KeyCode::Right, KeyCode::Char('l')],
+    ),
+    ("left", Action::Left, &[KeyCode::Left, KeyCode::Char('h')]),
+    ("move_right", Action::MoveRight, &[KeyCode::Char('>')]),
+    ("move_left", Action::MoveLeft, &[KeyCode::Char('<')]),
+    ("pin", Action::Pin, &[KeyCode::Char('P')]),
+    ("mute", Action::Mute, &[KeyCode::Char('m')]),
+    ("mute_problems", Action::MuteProblems, &[KeyCode::Char('x')]),
+    ("unmute_all", Action::UnmuteAll, &[KeyCode::Char('M')]),
+    ("redact", Action::Redact, &[KeyCode::Char('r')]),
+    ("grid", Action::Grid, &[KeyCode::Char('g')]),
+    ("unified", Action::Unified, &[KeyCode::Char('u')]),
+    ("sort", Action::Sort, &[KeyCode::Char('s')]),
+    ("report", Action::Report, &[KeyCode::Char('e')]),
+    ("snapshot", Action::Snapshot, &[KeyCode::Char('y')]),
+    ("resume", Action::Resume, &[KeyCode::Char(' ')]),
+    ("palette", Action::Palette, &[KeyCode::Char(':')]),
+    ("toggle_group", Action::ToggleGroup, &[KeyCode::Enter]),
+    ("follow", Action::Follow, &[KeyCode::Char('f')]),
+    ("payload", Action::Payload, &[KeyCode::Char('v')]),
+    ("density", Action::Density, &[KeyCode::Char('d')]),
+    ("note", Action::Note, &[KeyCode::Char('a')]),
+    ("resubscribe", Action::Resubscribe, &[KeyCode::Char('R')]),
+    (
+        "collapse_active",
+        Action::CollapseActive,
+        &[KeyCode::Char('A')],
+    ),
+    ("back", Action::Back, &[KeyCode::Esc]),
+];
+
+// The help popup, one line for one or a pair of actions.
+const HELP: [(&[Action], &str); 29] = [
+    (&[Action::Quit], "quit (also Ctrl-C)"),
+    (&[Action::Up, Action::Down], "select interface"),
+    (&[Action::Left, Action::Right], "focus column"),
+    (
+        &[Action::MoveLeft, Action::MoveRight],
+        "move the focused column",
+    ),
+    (
+        &[Action::NextProblem, Action::PreviousProblem],
+        "jump to the next/previous unhealthy interface",
+    ),
+    (&[Action::Pin], "pin/unpin the selected interface"),
+    (
+        &[Action::Mute],
+        "mute/unmute the selected interface, on this screen only",
+    ),
+    (
+        &[Action::Note],
+        "annotate the selected interface (Enter: save, empty removes)",
+    ),
+    (
+        &[Action::MuteProblems],
+        "mute every interface that is a problem now, new ones still show",
+    ),
+    (&[Action::UnmuteAll], "unmute all interfaces"),
+    (&[Action::History], "toggle the history view"),
+    (&[Action::Grid], "toggle showing columns as grids"),
+    (&[Action::Unified], "toggle a single list of every type"),
+    (&[Action::Density], "toggle compact/comfortable rows"),
+    (
+        &[Action::CollapseActive],
+        "collapse the active interfaces into a count line",
+    ),
+    (
+        &[Action::Sort],
+        "sort the single list by name, type, state or age",
+    ),
+    (&[Action::Report], "check the interfaces against --expected"),
+    (
+        &[Action::Follow],
+        "select whatever changed last, paused while navigating",
+    ),
+    (
+        &[Action::Redact],
+        "hide/show interface names, for screenshots",
+    ),
+    (&[Action::Snapshot], "copy the shown interfaces as a table"),
+    (
+        &[Action::Resubscribe],
+        "subscribe to the topics again, when updates stopped",
+    ),
+    (
+        &[Action::Payload],
+        "show/hide the last payload of the selected interface",
+    ),
+    (
+        &[Action::ToggleGroup],
+        "collapse/expand the group of the selected header",
+    ),
+    (
+        &[Action::CollapseRuns],
+        "collapse repeated transitions in the history view",
+    ),
+    (
+        &[Action::Filter],
+        "filter interfaces by name, or tag with #tag (Tab: fuzzy/substring, Enter: done)",
+    ),
+    (
+        &[Action::Resume],
+        "resume after --pause-on-error froze the view",
+    ),
+    (
+        &[Action::Back],
+        "clear the filter / close this help or the report",
+    ),
+    (
+        &[Action::Palette],
+        "search and run any action (also Ctrl-P)",
+    ),
+    (&[Action::Help], "toggle this help"),
+];
+
+/// An action as the command palette lists it.
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub action: Action,
+    pub name: String,
+    pub description: &'static str,
+    pub keys: String,
+}
+
+/// The keys bound to an action in the config, one or several.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Keys {
+    One(String),
+    Several(Vec<String>),
+}
+
+impl Keys {
+    fn names(&self) -> &[String] {
+        match self {
+            Keys::One(name) => std::slice::from_ref(name),
+            Keys::Several(names) => names,
+        }
+    }
+}
+
+/// Which key triggers which action. Binding an action in the config replaces
+/// its default keys, and takes the keys it is bound to away from the
+/// defaults of other actions, which keep the rest of theirs.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    actions: HashMap<KeyCode, Action>,
+    keys: Vec<(Action, Vec<KeyCode>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::new(&BTreeMap::new()).expect("the default keys don't conflict")
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap from the `[keys]` config section, rejecting unknown
+    /// actions and keys, and keys the config binds to more than one action.
+    pub fn new(bindings: &BTreeMap<String, Keys>) -> Result<Keymap, String> {
+        if let Some(name) = bindings
+            .keys()
+            .find(|name| !ACTIONS.iter().any(|(known, _, _)| known == name))
+        {
+            return Err(format!("unknown action '{}'", name));
+        }
+        let mut keymap = Keymap {
+            actions: HashMap::new(),
+            keys: Vec::new(),
+        };
+        // The bound keys first, so that the defaults only get what is left.
+        let mut bound = HashMap::new();
+        for (name, action, _) in ACTIONS {
+            let Some(keys) = bindings.get(name) else {
+                continue;
+            };
+            let keys = keys
+                .names()
+                .iter()
+                .map(|key| parse_key(key).ok_or_else(|| format!("{}: unknown key '{}'", name, key)))
+                .collect::<Result<Vec<_>, _>>()?;
+            for &key in &keys {
+                if let Some(other) = keymap.actions.insert(key, action) {
+                    if other != action {
+                        return Err(format!(
+                            "key '{}' is bound to both {} and {}",
+                            key_name(key),
+                            action_name(other),
+                            name
+                        ));
+                    }
+                }
+            }
+            bound.insert(name, keys);
+        }
+        for (name, action, defaults) in ACTIONS {
+            let keys = match bound.remove(name) {
+                Some(keys) => keys,
+                None => defaults
+                    .iter()
+                    .copied()
+                    .filter(|key| !keymap.actions.contains_key(key))
+                    .collect(),
+            };
+            for &key in &keys {
+                keymap.actions.entry(key).or_insert(action);
+            }
+            keymap.keys.push((action, keys));
+        }
+        Ok(keymap)
+    }
+
+    /// The action of a key pressed alone or with Shift, as for capitals;
+    /// with Ctrl or Alt it is not the bound key.
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        if !KeyModifiers::SHIFT.contains(key.modifiers) {
+            return None;
+        }
+        self.actions.get(&key.code).copied()
+    }
+
+    /// The lines of the help popup, with the keys as they are bound.
+    pub fn help(&self) -> Vec<String> {
+        HELP.iter()
+            .map(|(actions, description)| {
+                let keys: Vec<String> = actions
+                    .iter()
+                    .map(|action| {
+                        let names: Vec<String> =
+                            self.keys_of(*action).iter().map(|k| key_name(*k)).collect();
+                        names.join("/")
+                    })
+                    .collect();
+                format!("{:<8} {}", keys.join(", "), description)
+            })
+            .collect()
+    }
+
+    /// Every action but the palette itself, in the order of the config
+    /// section, described as in the help popup.
+    pub fn commands(&self) -> Vec<Command> {
+        ACTIONS
+            .iter()
+            .filter(|(_, action, _)| *action != Action::Palette)
+            .map(|&(name, action, _)| {
+                let description = HELP
+                    .iter()
+                    .find(|(actions, _)| actions.contains(&action))
+                    .map_or("", |(_, description)| description);
+                let keys: Vec<String> = self.keys_of(action).iter().map(|k| key_name(*k)).collect();
+                Command {
+                    action,
+                    name: name.replace('_', " "),
+                    description,
+                    keys: keys.join("/"),
+                }
+            })
+            .collect()
+    }
+
+    /// The first key bound to an action, for hints in the UI.
+    pub fn key(&self, action: Action) -> String {
+        self.keys_of(action)
+            .first()
+            .map_or("unbound".to_string(), |key| key_name(*key))
+    }
+
+    fn keys_of(&self, action: Action) -> &[KeyCode] {
+        self.keys
+            .iter()
+            .find(|(other, _)| *other == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+}
+
+fn action_name(action: Action) -> &'static str {
+    ACTIONS
+        .iter()
+        .find(|(_, other, _)| *other == action)
+        .map_or("?", |(name, _, _)| name)
+}
+
+/// Parses a key from the config: a single character, or the name of a
+/// special key such as "up" or "esc", case-insensitively.
+fn parse_key(key: &str) -> Option<KeyCode> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let code = match key.to_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "esc" => KeyCode::Esc,
+        "enter" => KeyCode::Enter,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        _ => return None,
+    };
+    Some(code)
+}
+
+fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap(toml: &str) -> Result<Keymap, String> {
+        Keymap::new(&toml::from_str(toml).unwrap())
+    }
+
+    #[test]
+    fn a_binding_takes_its_key_from_a_default() {
+        let keymap = keymap(
+            r#"quit = "x"
+down = "n""#,
+        )
+        .unwrap();
+        assert_eq!(keymap.action(KeyCode::Char('x').into()), Some(Action::Quit));
+        assert_eq!(keymap.action(KeyCode::Char('n').into()), Some(Action::Down));
+        assert_eq!(keymap.key(Action::MuteProblems), "unbound");
+        assert!(!keymap
+            .keys_of(Action::NextProblem)
+            .contains(&KeyCode::Char('n')));
+    }
+
+    #[test]
+    fn two_bindings_of_one_key_conflict() {
+        let error = keymap(
+            r#"quit = "x"
+help = "x""#,
+        )
+        .unwrap_err();
+        assert!(error.contains("bound to both"), "{}", error);
+    }
+
+    #[test]
+    fn keys_with_ctrl_or_alt_are_not_bound() {
+        let keymap = Keymap::default();
+        let key = |code, modifiers| keymap.action(KeyEvent::new(code, modifiers));
+        assert_eq!(
+            key(KeyCode::Char('q'), KeyModifiers::NONE),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            key(KeyCode::Char('M'), KeyModifiers::SHIFT),
+            Some(Action::UnmuteAll)
+        );
+        assert_eq!(key(KeyCode::Char('c'), KeyModifiers::CONTROL), None);
+        assert_eq!(key(KeyCode::Char('q'), KeyModifiers::ALT), None);
+    }
+}
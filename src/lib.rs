@@ -0,0 +1,799 @@
+mod app;
+mod capture;
+mod cli;
+mod clipboard;
+mod config;
+#[cfg(feature = "dbus")]
+mod dbus;
+mod demo;
+mod diagnostics;
+mod dry_run;
+mod encoding;
+mod error;
+mod expected;
+mod graph;
+#[cfg(feature = "health")]
+mod health;
+mod history;
+mod keymap;
+mod link;
+mod log_sink;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod name_filter;
+mod prefs;
+mod publisher;
+mod reload;
+mod self_bench;
+mod theme;
+mod toast;
+mod transform;
+mod ui;
+mod zone;
+
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use r2r::QosProfile;
+use std::borrow::Cow;
+use std::io::IsTerminal;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::signal::unix::SignalKind;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+use app::{App, InterfaceState, Stats};
+use config::Config;
+use encoding::{DecodeError, Encoding};
+use error::MonitorError;
+use expected::Expected;
+use graph::{Discovery, Watched};
+use keymap::Keymap;
+use link::Arrivals;
+use name_filter::NameFilter;
+use reload::Reloader;
+use transform::Transform;
+use ui::{spawn_monitor, UiOptions};
+
+/// Entry points for the benchmarks in `benches/`, not an interface of
+/// the crate.
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::app::{App, InterfaceState};
+    pub use crate::config::{Limits, StateValues};
+    pub use crate::encoding::Encoding;
+    pub use crate::name_filter::NameFilter;
+    pub use crate::ui::Offscreen;
+
+    use futures::{Stream, StreamExt};
+    use std::sync::{Arc, RwLock};
+    use tokio::task::JoinHandle;
+
+    /// Feeds JSON messages through the pipeline of a subscriber into
+    /// `shared_app`: parsed without the lock, queued and applied by the
+    /// merger.
+    pub fn spawn_pipeline(
+        messages: impl Stream<Item = Vec<u8>> + Send + 'static,
+        shared_app: &Arc<RwLock<App>>,
+    ) -> JoinHandle<()> {
+        crate::spawn_pipeline(messages.boxed(), Encoding::Json, None, None, shared_app)
+    }
+}
+
+// How often the node is spun, bounds the latency of incoming states.
+const SPIN_PERIOD: Duration = Duration::from_millis(10);
+
+// How often the ROS graph is asked how many publishers advertise the topic.
+const GRAPH_POLL_PERIOD: Duration = Duration::from_secs(1);
+
+// How long the tasks get to stop on exit before the program ends without them.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Runs the monitor as the command line asks, until it is quit or
+/// interrupted.
+pub async fn run() -> Result<(), MonitorError> {
+    let started = Instant::now();
+    let started_at = SystemTime::now();
+    let cli = cli::Cli::parse_args();
+    match cli.command {
+        Some(cli::Command::PublishTest(args)) => return publisher::run(args).await,
+        Some(cli::Command::SelfBench(args)) => return self_bench::run(args),
+        None => {}
+    }
+    let name_filter = NameFilter::new(&cli.include, &cli.exclude)?;
+    let watched = Watched::new(&cli.watch_service, &cli.watch_action);
+    let group_by = cli
+        .group_by
+        .as_deref()
+        .map(ui::parse_group_by)
+        .transpose()?;
+    let subgroup_by = cli
+        .subgroup_by
+        .as_deref()
+        .map(ui::parse_group_by)
+        .transpose()?;
+    let mut config = match &cli.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    let expected = match &cli.expected {
+        Some(path) => Expected::load(path)?,
+        None => Expected::default(),
+    };
+    let keymap =
+        Keymap::new(&config.keys).map_err(|e| MonitorError::Config(format!("keys: {}", e)))?;
+    let transform = config
+        .transform
+        .as_deref()
+        .map(Transform::new)
+        .transpose()
+        .map_err(|e| MonitorError::Config(format!("transform: {}", e)))?
+        .map(Arc::new);
+    if let Some(topic) = &expected.topic {
+        config.topic = topic.clone();
+    }
+
+    let mut ui_options = UiOptions {
+        no_alt_screen: cli.no_alt_screen,
+        max_fps: cli.max_fps,
+        colors: match cli.color {
+            cli::ColorMode::Auto => ui::detect_colors(),
+            cli::ColorMode::Always => true,
+            cli::ColorMode::Never => false,
+        },
+        ascii: cli.ascii || !ui::detect_unicode(),
+        theme: cli
+            .base16
+            .as_deref()
+            .map(theme::Theme::load_base16)
+            .transpose()?,
+        flash: reload::flash(&config),
+        stale: reload::stale(&config),
+        banner: config.banner.clone(),
+        density: config.ui.density,
+        severity: config.severity.clone(),
+        group_by,
+        subgroup_by,
+        topics: cli.compare.clone(),
+        redact: cli.redact,
+        pause_on_error: cli.pause_on_error,
+        zone: cli.tz,
+        grid: cli.grid,
+        collapse_active: cli.collapse_active,
+        unified: cli.unified,
+        follow: cli.follow,
+        expanded_help: cli.expanded_help,
+        keymap,
+        reloads: None,
+        resubscribe: None,
+    };
+
+    if cli.demo {
+        let shared_apps = [Arc::new(RwLock::new(demo::app(name_filter)))];
+        return spawn_monitor(&shared_apps, ui_options).await;
+    }
+    if let Some(wait) = cli.dry_run {
+        return dry_run::run(&cli, &config, &expected, transform, name_filter, wait).await;
+    }
+
+    let node = create_node("monitor")?;
+    let arc_node = Arc::new(Mutex::new(node));
+
+    // One app per monitored topic, two side by side with `--compare`.
+    let topics = if cli.compare.is_empty() {
+        vec![config.topic.clone()]
+    } else {
+        cli.compare.clone()
+    };
+    let mut shared_apps = Vec::new();
+    let mut subscriptions = Vec::new();
+    // Every task that runs until the program ends, stopped on exit.
+    let mut tasks = Vec::new();
+    for topic in &topics {
+        let mut app = App::new(
+            name_filter.clone(),
+            config.states.clone(),
+            config.limits.clone(),
+        );
+        for interface in &expected.interfaces {
+            app.expect(interface.interface_type.as_str(), &interface.name);
+        }
+        // Written by the merger and the spin task, while the readers taking
+        // snapshots of it, like the UI, don't block each other.
+        let shared_app = Arc::new(RwLock::new(app));
+        if cli.from_graph {
+            // The interfaces come from polling the graph, not from a topic.
+            subscriptions.push(None);
+            shared_apps.push(shared_app);
+            continue;
+        }
+        // Create the subscription before the node is spun or the UI is up, so that
+        // no message published after startup can arrive before we listen for it.
+        // Headless there is nobody to tell, with the UI the footer tells it.
+        match spawn_subscriber(
+            arc_node.clone(),
+            topic,
+            config.qos(topic),
+            cli.encoding,
+            transform.clone(),
+            &shared_app,
+        )
+        .await
+        {
+            Err(e) if cli.log_mode => return Err(e),
+            Err(e) => {
+                r2r::log_error!("monitor", "Subscribing failed, {}.", e);
+                shared_app
+                    .write()
+                    .unwrap()
+                    .set_subscription_error(e.to_string());
+                subscriptions.push(None);
+            }
+            Ok(subscription) => subscriptions.push(Some(subscription)),
+        }
+        shared_apps.push(shared_app);
+    }
+
+    // MQTT feeds the first topic's interfaces, next to whatever ROS does.
+    #[cfg(feature = "mqtt")]
+    if let (Some(broker), Some(topic)) = (&cli.mqtt, &cli.mqtt_topic) {
+        let messages = mqtt::subscribe(broker, topic)?;
+        tasks.push(spawn_pipeline(
+            messages,
+            Encoding::Json,
+            None,
+            Some(mqtt::ORIGIN),
+            &shared_apps[0],
+        ));
+    }
+
+    #[cfg(feature = "health")]
+    if let Some(port) = cli.health_port {
+        tasks.push(
+            health::serve(port, topics.clone(), shared_apps.clone())
+                .await
+                .map_err(|source| MonitorError::Health { port, source })?,
+        );
+    }
+
+    // A reloaded config may change the topics while running.
+    let topics = Arc::new(RwLock::new(topics));
+
+    #[cfg(feature = "dbus")]
+    if let Some(bus) = cli.dbus {
+        tasks.push(dbus::serve(bus, topics.clone(), shared_apps.clone(), started_at).await?);
+    }
+
+    if let Some(topic) = &cli.diagnostics_topic {
+        tasks.push(
+            diagnostics::spawn(arc_node.clone(), topic, topics.clone(), shared_apps.clone())
+                .map_err(|source| MonitorError::Publish {
+                    topic: topic.clone(),
+                    source,
+                })?,
+        );
+    }
+
+    let (ui, reloads) = tokio::sync::mpsc::unbounded_channel();
+    let (resubscribe, resubscribe_requests) = tokio::sync::mpsc::unbounded_channel();
+    ui_options.reloads = Some(reloads);
+    // With `--from-graph` there is no subscription to renew.
+    ui_options.resubscribe = (!cli.from_graph).then_some(resubscribe);
+    let reloader = Reloader {
+        path: cli.config.clone(),
+        config: config.clone(),
+        transform: transform.clone(),
+        expected: expected.clone(),
+        compare: !cli.compare.is_empty(),
+        from_graph: cli.from_graph,
+        encoding: cli.encoding,
+        arc_node: arc_node.clone(),
+        topics: topics.clone(),
+        shared_apps: shared_apps.clone(),
+        subscriptions,
+        ui,
+    };
+    tasks.push(tokio::task::spawn(reloader.run(resubscribe_requests)));
+
+    // Tells the tasks that write files to flush what they have and stop.
+    let (stop, stopping) = tokio::sync::watch::channel(false);
+
+    let shared_apps_clone = shared_apps.clone();
+    let mut monitor = if cli.log_mode {
+        if let (Some(path), Some(lines)) = (&cli.record, cli.tail) {
+            log_sink::print_tail(path, lines)?;
+        }
+        let record = cli
+            .record
+            .as_deref()
+            .map(log_sink::open_record)
+            .transpose()?;
+        // Piped, the lines stay plain for grep and the like.
+        let colors = match cli.color {
+            cli::ColorMode::Auto => std::io::stdout().is_terminal() && ui::detect_colors(),
+            cli::ColorMode::Always => true,
+            cli::ColorMode::Never => false,
+        };
+        let painter = colors.then(|| log_sink::Painter {
+            theme: ui_options.theme.clone(),
+        });
+        tokio::task::spawn(async move {
+            if let Err(e) = log_sink::run(
+                &shared_apps_clone[0],
+                cli.log_format,
+                painter,
+                record,
+                stopping,
+            )
+            .await
+            {
+                // A closed stdout means whoever read the log is done with it.
+                if e.kind() != std::io::ErrorKind::BrokenPipe {
+                    r2r::log_error!("monitor", "Could not write the log: '{}'.", e);
+                }
+            }
+            Ok(())
+        })
+    } else {
+        tokio::task::spawn(async move { spawn_monitor(&shared_apps_clone, ui_options).await })
+    };
+
+    // r2r has no executor of its own, so the node is spun cooperatively on the
+    // runtime: polled without a timeout at a short period instead of in a
+    // blocking thread.
+    let arc_node_clone: Arc<Mutex<r2r::Node>> = arc_node.clone();
+    let shared_apps_clone = shared_apps.clone();
+    let topics_clone = topics.clone();
+    let mut discovery = cli.from_graph.then(Discovery::default);
+    tasks.push(tokio::task::spawn(async move {
+        let mut spin = tokio::time::interval(SPIN_PERIOD);
+        let mut last_graph_poll: Option<Instant> = None;
+        loop {
+            spin.tick().await;
+            let (publishers, availability) = {
+                let mut node = arc_node_clone.lock().unwrap();
+                node.spin_once(Duration::ZERO);
+                if last_graph_poll.is_none_or(|t| t.elapsed() >= GRAPH_POLL_PERIOD) {
+                    last_graph_poll = Some(Instant::now());
+                    let publishers = topics_clone
+                        .read()
+                        .unwrap()
+                        .iter()
+                        .map(|topic| {
+                            // With `--from-graph` no topic is monitored.
+                            if discovery.is_some() {
+                                return None;
+                            }
+                            node.get_publishers_info_by_topic(topic, false)
+                                .ok()
+                                .map(|info| info.len())
+                        })
+                        .collect();
+                    let watched = (!watched.is_empty()).then(|| watched.availability(&node));
+                    let discovered = discovery.as_mut().map(|discovery| discovery.poll(&node));
+                    let availability = watched
+                        .into_iter()
+                        .chain(discovered)
+                        .flat_map(|presences| {
+                            presences.unwrap_or_else(|e| {
+                                r2r::log_warn!(
+                                    "monitor",
+                                    "Could not query the ROS graph: '{}'.",
+                                    e
+                                );
+                                Vec::new()
+                            })
+                        })
+                        .collect();
+                    (publishers, availability)
+                } else {
+                    (vec![None; shared_apps_clone.len()], Vec::new())
+                }
+            };
+
+            for (shared_app, publishers) in shared_apps_clone.iter().zip(publishers) {
+                let mut app = shared_app.write().unwrap();
+                app.mark_spinning();
+                if let Some(count) = publishers {
+                    app.set_publisher_count(count);
+                }
+                for presence in &availability {
+                    *app = std::mem::take(&mut *app).update_availability(presence.clone());
+                }
+            }
+        }
+    }));
+
+    // Quitting the monitor ends the program. The log sink runs until the
+    // process is interrupted or terminated, or until `--capture` is over.
+    let mut terminate = tokio::signal::unix::signal(SignalKind::terminate())?;
+    let capture_over = async {
+        match cli.capture {
+            Some(window) => tokio::time::sleep(window).await,
+            None => std::future::pending().await,
+        }
+    };
+    let interrupted = tokio::select! {
+        // A UI that failed, like on a terminal it could not set up, ends
+        // the program with its error.
+        result = &mut monitor => {
+            result??;
+            false
+        }
+        _ = tokio::signal::ctrl_c() => true,
+        _ = terminate.recv() => true,
+        _ = capture_over => true,
+    };
+    let _ = stop.send(true);
+    if interrupted && !cli.log_mode {
+        // Stop the UI before restoring the terminal it draws on.
+        monitor.abort();
+    }
+    let stopped = tokio::time::timeout(SHUTDOWN_TIMEOUT, async {
+        // A monitor that was quit has already ended.
+        if interrupted {
+            let _ = monitor.await;
+        }
+        if let Err(e) = stop_tasks(tasks).await {
+            r2r::log_warn!("monitor", "Could not stop the tasks cleanly: '{}'.", e);
+        }
+    })
+    .await;
+    if interrupted {
+        ui::restore_terminal();
+    }
+    if stopped.is_err() {
+        r2r::log_warn!(
+            "monitor",
+            "Exiting without the tasks that did not stop within {}s.",
+            SHUTDOWN_TIMEOUT.as_secs()
+        );
+    }
+
+    if cli.capture.is_some() {
+        // Quitting or interrupting early reports what was seen until then.
+        let report = capture::report(
+            &topics.read().unwrap(),
+            &shared_apps,
+            started_at,
+            started.elapsed(),
+        );
+        capture::write(&report, cli.out.as_deref())?;
+    }
+
+    if cli.exit_summary {
+        // Compared topics are summed up, except for the peak of errors.
+        let stats = shared_apps
+            .iter()
+            .map(|app| app.read().unwrap().stats)
+            .fold(Stats::default(), |total, stats| Stats {
+                messages: total.messages + stats.messages,
+                parse_errors: total.parse_errors + stats.parse_errors,
+                transform_errors: total.transform_errors + stats.transform_errors,
+                encoding_mismatches: total.encoding_mismatches + stats.encoding_mismatches,
+                interfaces_seen: total.interfaces_seen + stats.interfaces_seen,
+                errors: total.errors + stats.errors,
+                max_errors: total.max_errors.max(stats.max_errors),
+                decimated: total.decimated + stats.decimated,
+            });
+        let summary = serde_json::json!({
+            "messages": stats.messages,
+            "parse_errors": stats.parse_errors,
+            "transform_errors": stats.transform_errors,
+            "encoding_mismatches": stats.encoding_mismatches,
+            "interfaces_seen": stats.interfaces_seen,
+            "max_errors": stats.max_errors,
+            "decimated": stats.decimated,
+            "runtime_seconds": started.elapsed().as_secs_f64(),
+        });
+        eprintln!("{}", summary);
+    }
+
+    Ok(())
+}
+
+/// Creates a node, explaining a failure instead of bubbling up the raw error.
+fn create_node(name: &str) -> Result<r2r::Node, MonitorError> {
+    let ctx = r2r::Context::create().map_err(|source| MonitorError::RosSetup {
+        what: "context".to_string(),
+        source,
+    })?;
+    r2r::Node::create(ctx, name, "").map_err(|source| MonitorError::RosSetup {
+        what: format!("node '{}'", name),
+        source,
+    })
+}
+
+/// Prints an error for the user, not as the debug output of returning it from `main`.
+pub fn exit_with(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
+}
+
+/// Aborts the tasks and waits for all of them to end, failing with the first
+/// that panicked rather than was aborted.
+async fn stop_tasks(tasks: Vec<JoinHandle<()>>) -> Result<(), MonitorError> {
+    for task in &tasks {
+        task.abort();
+    }
+    let mut result = Ok(());
+    for task in tasks {
+        match task.await {
+            Err(e) if e.is_panic() && result.is_ok() => result = Err(MonitorError::Task(e)),
+            _ => {}
+        }
+    }
+    result
+}
+
+/// What a subscriber hands to the merger of its app.
+enum Update {
+    // A message arrived, whatever it holds, with when the messages of the
+    // topic arrived so far.
+    Message(Option<Arrivals>),
+    State(InterfaceState),
+    ParseError,
+    TransformError,
+    // A message in another encoding than the configured one.
+    EncodingMismatch(Encoding),
+}
+
+// The most updates applied under one lock of the app, bounds how long the UI can wait for it.
+const MAX_MERGE_BATCH: usize = 1024;
+
+async fn spawn_subscriber(
+    arc_node: Arc<Mutex<r2r::Node>>,
+    topic: &str,
+    qos: QosProfile,
+    encoding: Encoding,
+    transform: Option<Arc<Transform>>,
+    shared_app: &Arc<RwLock<App>>,
+) -> Result<JoinHandle<()>, MonitorError> {
+    let failed = |source| MonitorError::Subscription {
+        topic: topic.to_string(),
+        source,
+    };
+    // JSON is text, the binary encodings need a message type that carries bytes.
+    let subscriber = {
+        let mut node = arc_node.lock().unwrap();
+        match encoding {
+            Encoding::Json => node
+                .subscribe::<r2r::std_msgs::msg::String>(topic, qos)
+                .map_err(failed)?
+                .map(|msg| msg.data.into_bytes())
+                .boxed(),
+            Encoding::Msgpack | Encoding::Cbor => node
+                .subscribe::<r2r::std_msgs::msg::UInt8MultiArray>(topic, qos)
+                .map_err(failed)?
+                .map(|msg| msg.data)
+                .boxed(),
+        }
+    };
+
+    // Aborting it unsubscribes.
+    Ok(spawn_pipeline(
+        subscriber, encoding, transform, None, shared_app,
+    ))
+}
+
+/// Parses the messages of a source into an app until the returned task is
+/// aborted, tagging the states with the source's origin if it has one.
+fn spawn_pipeline(
+    messages: BoxStream<'static, Vec<u8>>,
+    encoding: Encoding,
+    transform: Option<Arc<Transform>>,
+    origin: Option<&'static str>,
+    shared_app: &Arc<RwLock<App>>,
+) -> JoinHandle<()> {
+    // Messages are parsed without the lock and queued, a single merger per app
+    // applies them in batches, so the lock is taken once per batch and by one
+    // writer instead of by every message.
+    let (updates, queue) = tokio::sync::mpsc::unbounded_channel();
+    let shared_app = shared_app.clone();
+    // Both run in the one task, so that aborting or awaiting its handle
+    // covers the merger too. The merger ends once the parsing did and the
+    // queue is drained.
+    tokio::task::spawn(async move {
+        let parsing = async {
+            let result = subscriber_callback(messages, encoding, transform, origin, updates).await;
+            if let Err(e) = result {
+                r2r::log_error!("monitor", "Monitor subscriber failed with: '{}'.", e);
+                shared_app
+                    .write()
+                    .unwrap()
+                    .set_subscription_error(e.to_string());
+            }
+        };
+        futures::join!(parsing, merge_updates(queue, &shared_app));
+    })
+}
+
+async fn subscriber_callback(
+    mut subscriber: BoxStream<'static, Vec<u8>>,
+    encoding: Encoding,
+    transform: Option<Arc<Transform>>,
+    origin: Option<&'static str>,
+    updates: UnboundedSender<Update>,
+) -> Result<(), MonitorError> {
+    let mut arrivals: Option<Arrivals> = None;
+    loop {
+        match subscriber.next().await {
+            Some(data) => {
+                let now = Instant::now();
+                match &mut arrivals {
+                    Some(arrivals) => arrivals.record(now),
+                    None => arrivals = Some(Arrivals::new(now)),
+                }
+                // Another source feeding the app, like MQTT, would mix its
+                // arrivals into those of the topic.
+                let link = arrivals.filter(|_| origin.is_none());
+                // A batch is queued state by state while it is parsed.
+                updates
+                    .send(Update::Message(link))
+                    .map_err(|_| MonitorError::MergerStopped)?;
+                let mut stopped = false;
+                // What can't be decoded is left to the parsing to report.
+                let transformed = transform.as_deref().and_then(|transform| {
+                    let input = encoding.decode(&data).ok()?;
+                    Some(transform.apply(input))
+                });
+                // The transform outputs JSON, whatever the messages are encoded in.
+                let decoded = match transformed {
+                    None => Some((encoding, Cow::Borrowed(data.as_slice()))),
+                    Some(Ok(json)) => Some((Encoding::Json, Cow::Owned(json.into_bytes()))),
+                    Some(Err(e)) => {
+                        r2r::log_debug!(
+                            "monitor",
+                            "Dropped a state message, {}: '{}'.",
+                            e,
+                            encoding.display(&data)
+                        );
+                        stopped |= updates.send(Update::TransformError).is_err();
+                        None
+                    }
+                };
+                let result = decoded.map_or(Ok(()), |(encoding, data)| {
+                    encoding.parse_each(&data, |state| {
+                        let update = match state {
+                            Ok(mut interface_state) => {
+                                if let Some(origin) = origin {
+                                    let tags = interface_state.tags.get_or_insert_with(Vec::new);
+                                    tags.push(origin.to_string());
+                                }
+                                Update::State(interface_state)
+                            }
+                            Err(e) => {
+                                r2r::log_debug!("monitor", "Dropped a state, {}.", e);
+                                Update::ParseError
+                            }
+                        };
+                        stopped |= updates.send(update).is_err();
+                    })
+                });
+                match result {
+                    Ok(()) => (),
+                    Err(DecodeError::Mismatch(other)) => {
+                        r2r::log_warn!(
+                            "monitor",
+                            "Dropped a state message, it is {} but --encoding is {}.",
+                            other.name(),
+                            encoding.name()
+                        );
+                        stopped |= updates.send(Update::EncodingMismatch(other)).is_err();
+                    }
+                    Err(e) => {
+                        r2r::log_debug!(
+                            "monitor",
+                            "Dropped a state message, {}: '{}'.",
+                            e,
+                            encoding.display(&data)
+                        );
+                        stopped |= updates.send(Update::ParseError).is_err();
+                    }
+                }
+                if stopped {
+                    return Err(MonitorError::MergerStopped);
+                }
+                // A flooded topic may never leave the stream pending, give the
+                // merger and the UI their turn.
+                tokio::task::yield_now().await;
+            }
+            // Ending the loop here, instead of polling the finished stream
+            // over and over, leaves the runtime to the other tasks.
+            None => return Err(MonitorError::SubscriptionEnded),
+        }
+    }
+}
+
+/// Applies queued updates in arrival order, as many as are waiting at once.
+async fn merge_updates(mut queue: UnboundedReceiver<Update>, shared_app: &Arc<RwLock<App>>) {
+    let mut batch = Vec::with_capacity(MAX_MERGE_BATCH);
+    while queue.recv_many(&mut batch, MAX_MERGE_BATCH).await > 0 {
+        let mut app = shared_app.write().unwrap();
+        for update in batch.drain(..) {
+            match update {
+                Update::Message(arrivals) => {
+                    app.stats.messages += 1;
+                    if arrivals.is_some() {
+                        app.arrivals = arrivals;
+                    }
+                }
+                Update::State(interface_state) => {
+                    *app = std::mem::take(&mut *app).update_state(interface_state)
+                }
+                Update::ParseError => app.stats.parse_errors += 1,
+                Update::TransformError => app.stats.transform_errors += 1,
+                Update::EncodingMismatch(other) => {
+                    app.stats.encoding_mismatches += 1;
+                    app.mismatched_encoding = Some(other);
+                    app.generation += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared_app() -> Arc<RwLock<App>> {
+        Arc::new(RwLock::new(App::new(
+            NameFilter::default(),
+            config::StateValues::default(),
+            config::Limits::default(),
+        )))
+    }
+
+    fn message(i: usize) -> Vec<u8> {
+        serde_json::json!({"name": format!("/a{}", i), "interface_type": "server", "state": "Active"})
+            .to_string()
+            .into_bytes()
+    }
+
+    #[tokio::test]
+    async fn stopping_ends_the_pipelines_with_their_mergers() {
+        let shared_app = shared_app();
+        // One source still flooding the app, one waiting for messages.
+        let flooding = futures::stream::iter((0..).map(message)).boxed();
+        let waiting = futures::stream::pending().boxed();
+        let tasks = vec![
+            spawn_pipeline(flooding, Encoding::Json, None, None, &shared_app),
+            spawn_pipeline(waiting, Encoding::Json, None, None, &shared_app),
+        ];
+        while shared_app.read().unwrap().stats.messages == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let stopped = tokio::time::timeout(SHUTDOWN_TIMEOUT, stop_tasks(tasks)).await;
+        assert!(matches!(stopped, Ok(Ok(()))));
+        // Nothing is left holding the app, neither a subscriber nor a merger.
+        assert_eq!(Arc::strong_count(&shared_app), 1);
+    }
+
+    #[tokio::test]
+    async fn a_task_that_panicked_fails_the_stop() {
+        let tasks = vec![
+            tokio::spawn(async { panic!("broken") }),
+            tokio::spawn(std::future::pending()),
+        ];
+        while !tasks[0].is_finished() {
+            tokio::task::yield_now().await;
+        }
+        let stopped = stop_tasks(tasks).await;
+        assert!(matches!(stopped, Err(MonitorError::Task(_))));
+    }
+
+    #[tokio::test]
+    async fn an_ended_source_is_merged_before_its_task_ends() {
+        let shared_app = shared_app();
+        let messages = futures::stream::iter((0..100).map(message)).boxed();
+        spawn_pipeline(messages, Encoding::Json, None, None, &shared_app)
+            .await
+            .unwrap();
+        let app = shared_app.read().unwrap();
+        assert_eq!(app.stats.messages, 100);
+        assert_eq!(app.stats.interfaces_seen, 100);
+        assert!(app.subscription_error.is_some());
+    }
+}
@@ -0,0 +1,174 @@
+use std::time::{Duration, Instant};
+
+// How much each new time between messages weighs in the running mean and
+// variance, the quality follows a change within some tens of messages.
+const WEIGHT: f64 = 0.1;
+
+// Messages needed before the spread of the times between them says anything.
+const MIN_MESSAGES: u64 = 5;
+
+/// The most signal bars the quality of a link is shown with.
+pub const BARS: usize = 4;
+
+// Expected periods without an update after which an interface is silent.
+const SILENT_PERIODS: f64 = 4.0;
+
+/// When the messages of a topic, or the updates of an interface, arrived, as
+/// a running mean and variance of the times between them. For a topic it
+/// tells how steadily the monitoring link itself delivers, whatever the
+/// states it carries.
+#[derive(Debug, Clone, Copy)]
+pub struct Arrivals {
+    last: Instant,
+    messages: u64,
+    // In seconds, weighted towards the latest times between messages.
+    mean: f64,
+    variance: f64,
+}
+
+impl Arrivals {
+    pub fn new(at: Instant) -> Arrivals {
+        Arrivals {
+            last: at,
+            messages: 1,
+            mean: 0.0,
+            variance: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, at: Instant) {
+        let interval = at.saturating_duration_since(self.last).as_secs_f64();
+        if self.messages == 1 {
+            self.mean = interval;
+        } else {
+            let deviation = interval - self.mean;
+            self.mean += WEIGHT * deviation;
+            self.variance = (1.0 - WEIGHT) * (self.variance + WEIGHT * deviation * deviation);
+        }
+        self.last = at;
+        self.messages += 1;
+    }
+
+    /// Out of `BARS`, how steadily messages arrive: all of them while the
+    /// times between them hardly vary, fewer the more they spread, and fewer
+    /// still while the next message is long overdue. `None` until enough
+    /// messages arrived to tell.
+    pub fn quality(&self, now: Instant) -> Option<usize> {
+        if self.messages < MIN_MESSAGES {
+            return None;
+        }
+        // Messages that arrive in bursts all at once are as steady as it gets.
+        if self.mean <= f64::EPSILON {
+            return Some(BARS);
+        }
+        let spread = self.variance.sqrt() / self.mean;
+        let steadiness = match spread {
+            s if s < 0.1 => 4,
+            s if s < 0.25 => 3,
+            s if s < 0.5 => 2,
+            s if s < 1.0 => 1,
+            _ => 0,
+        };
+        let overdue = self.since_last(now).as_secs_f64() / self.mean;
+        let gap = match overdue {
+            o if o < 2.0 => BARS,
+            o if o < 4.0 => 2,
+            o if o < 8.0 => 1,
+            _ => 0,
+        };
+        Some(steadiness.min(gap))
+    }
+
+    /// Arrivals per second as of `now`, slower while the next one is
+    /// overdue. `None` until enough arrived to tell.
+    pub fn rate(&self, now: Instant) -> Option<f64> {
+        if self.messages < MIN_MESSAGES {
+            return None;
+        }
+        let interval = self.mean.max(self.since_last(now).as_secs_f64());
+        (interval > f64::EPSILON).then(|| 1.0 / interval)
+    }
+
+    pub fn since_last(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.last)
+    }
+}
+
+/// How often an interface is expected to report, and how far off it may
+/// be as a fraction of `hz`, from `[[states.rates]]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    pub hz: f64,
+    pub tolerance: f64,
+}
+
+/// How the rate an interface reports at compares to the expected one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pace {
+    OnRate,
+    Slow,
+    Fast,
+    // Nothing arrived for several of the expected periods.
+    Silent,
+}
+
+impl Rate {
+    /// `None` while too few updates arrived to tell, unless the interface
+    /// has already been silent for long.
+    pub fn pace(&self, arrivals: &Arrivals, now: Instant) -> Option<Pace> {
+        if arrivals.since_last(now).as_secs_f64() * self.hz > SILENT_PERIODS {
+            return Some(Pace::Silent);
+        }
+        let observed = arrivals.rate(now)?;
+        Some(if observed < self.hz * (1.0 - self.tolerance) {
+            Pace::Slow
+        } else if observed > self.hz * (1.0 + self.tolerance) {
+            Pace::Fast
+        } else {
+            Pace::OnRate
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Arrivals at these times between messages, in milliseconds, with when
+    /// the last one arrived.
+    fn arriving(intervals: impl IntoIterator<Item = u64>) -> (Arrivals, Instant) {
+        let mut at = Instant::now();
+        let mut arrivals = Arrivals::new(at);
+        for interval in intervals {
+            at += Duration::from_millis(interval);
+            arrivals.record(at);
+        }
+        (arrivals, at)
+    }
+
+    #[test]
+    fn steady_arrivals_have_every_bar() {
+        let (arrivals, last) = arriving([100; 20]);
+        assert_eq!(arrivals.quality(last), Some(BARS));
+        let (arrivals, last) = arriving([0; 20]);
+        assert_eq!(arrivals.quality(last), Some(BARS));
+        let (arrivals, last) = arriving([100; 3]);
+        assert_eq!(arrivals.quality(last), None);
+    }
+
+    #[test]
+    fn bursty_arrivals_lose_bars() {
+        let (arrivals, last) = arriving([10, 500].repeat(20));
+        assert!(arrivals.quality(last).unwrap() <= 1);
+    }
+
+    #[test]
+    fn overdue_arrivals_lose_bars_the_longer_they_are_late() {
+        let (arrivals, last) = arriving([100; 20]);
+        let after = |millis| arrivals.quality(last + Duration::from_millis(millis));
+        assert_eq!(after(150), Some(BARS));
+        assert_eq!(after(300), Some(2));
+        assert_eq!(after(500), Some(1));
+        assert_eq!(after(1000), Some(0));
+    }
+}
@@ -0,0 +1,184 @@
+use crossterm::style::Stylize;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::sync::watch;
+
+use crate::app::{App, State, Transition};
+use crate::cli::LogFormat;
+use crate::error::MonitorError;
+use crate::theme::Theme;
+
+// How often new transitions are picked up from the app.
+const POLL_PERIOD: Duration = Duration::from_millis(100);
+
+/// Opens the `--record` file, lines are appended to what earlier runs wrote.
+pub fn open_record(path: &Path) -> Result<File, MonitorError> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|source| MonitorError::Write {
+            what: "record",
+            path: path.to_path_buf(),
+            source,
+        })
+}
+
+/// Prints the last `lines` lines of the `--record` file, for continuity
+/// with the earlier runs before the live transitions follow.
+pub fn print_tail(path: &Path, lines: usize) -> Result<(), MonitorError> {
+    let unreadable = |source| MonitorError::Read {
+        what: "record",
+        path: path.to_path_buf(),
+        source,
+    };
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(unreadable(e)),
+    };
+    let mut tail = VecDeque::with_capacity(lines);
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(unreadable)?;
+        if tail.len() == lines {
+            tail.pop_front();
+        }
+        if lines > 0 {
+            tail.push_back(line);
+        }
+    }
+    let mut out = io::stdout().lock();
+    for line in tail {
+        let _ = writeln!(out, "{}", line);
+    }
+    let _ = out.flush();
+    Ok(())
+}
+
+/// Colors the state each text line tells an interface went to, as the TUI
+/// colors it, in the colors of the `--base16` scheme if there is one.
+#[derive(Debug, Clone, Default)]
+pub struct Painter {
+    pub theme: Option<Theme>,
+}
+
+impl Painter {
+    fn paint(&self, text: &str, state: State) -> String {
+        let color = crate::ui::health_color(state.health());
+        let color = self
+            .theme
+            .as_ref()
+            .map_or(color, |theme| theme.foreground(color));
+        text.with(crossterm::style::Color::from(color)).to_string()
+    }
+}
+
+/// Prints a line for every transition until `stop` is signalled, in place
+/// of the interactive monitor, also appending it to `record` if given. Each
+/// poll's lines are flushed at once so that a pipe sees them right away.
+/// With a `painter` the text lines printed are colored, those recorded
+/// never are.
+/// Ends when stdout is closed, e.g. by a `| head` that has seen enough.
+///
+/// The lines come in the order the transitions were applied to the app, one
+/// source after another where several feed it, so the `seq` of the JSON
+/// lines always increases. It says nothing about the order the publishers
+/// sent them in, and a gap in it means that more transitions came within a
+/// poll than the app keeps.
+pub async fn run(
+    shared_app: &Arc<RwLock<App>>,
+    format: LogFormat,
+    painter: Option<Painter>,
+    mut record: Option<File>,
+    mut stop: watch::Receiver<bool>,
+) -> io::Result<()> {
+    let mut last_seq = None;
+    let mut stopping = false;
+    loop {
+        // Each line as recorded, and as printed where that differs.
+        let lines: Vec<(String, Option<String>)> = {
+            let app = shared_app.read().unwrap();
+            let new: Vec<&Transition> = app.transitions_after(last_seq).collect();
+            if let Some(last) = new.last() {
+                last_seq = Some(last.seq);
+            }
+            new.into_iter()
+                .map(|transition| match (format, &painter) {
+                    (LogFormat::Text, painter) => (
+                        format_transition(transition, None),
+                        painter
+                            .as_ref()
+                            .map(|painter| format_transition(transition, Some(painter))),
+                    ),
+                    (LogFormat::Json, _) => (json_transition(transition), None),
+                })
+                .collect()
+        };
+        if !lines.is_empty() {
+            let mut out = io::stdout().lock();
+            for (line, printed) in &lines {
+                writeln!(out, "{}", printed.as_ref().unwrap_or(line))?;
+            }
+            out.flush()?;
+            if let Some(file) = &mut record {
+                for (line, _) in &lines {
+                    writeln!(file, "{}", line)?;
+                }
+                file.flush()?;
+            }
+        }
+        if stopping {
+            return Ok(());
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_PERIOD) => (),
+            // One more pass writes what arrived since the last one.
+            _ = stop.changed() => stopping = true,
+        }
+    }
+}
+
+/// A transition as a line of JSON, `from` null for a new interface.
+fn json_transition(transition: &Transition) -> String {
+    let at = transition
+        .at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    serde_json::json!({
+        "seq": transition.seq,
+        "at": at,
+        "interface_type": transition.interface_type,
+        "name": transition.name,
+        "from": transition.from,
+        "to": transition.to,
+        "state": format!("{:?}", transition.state).to_lowercase(),
+    })
+    .to_string()
+}
+
+fn format_transition(transition: &Transition, painter: Option<&Painter>) -> String {
+    let at = transition
+        .at
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let to = match painter {
+        Some(painter) => painter.paint(&transition.to, transition.state),
+        None => transition.to.clone(),
+    };
+    match &transition.from {
+        Some(from) => format!(
+            "{:.3} {} {}: {} -> {}",
+            at, transition.interface_type, transition.name, from, to
+        ),
+        None => format!(
+            "{:.3} {} {}: {} (new)",
+            at, transition.interface_type, transition.name, to
+        ),
+    }
+}
@@ -0,0 +1,67 @@
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use std::time::Duration;
+
+use crate::error::MonitorError;
+
+/// The tag of every state read from MQTT, so the filter `#mqtt` tells the
+/// sources apart.
+pub const ORIGIN: &str = "mqtt";
+
+const DEFAULT_PORT: u16 = 1883;
+
+const KEEP_ALIVE: Duration = Duration::from_secs(5);
+
+// Requests queued to the client, only the subscription is ever sent.
+const REQUEST_CAPACITY: usize = 10;
+
+// How long to wait before connecting again after losing the broker.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The payloads published on `topic` at `broker`, given as `host` or
+/// `host:port`. The broker is connected to again whenever it is lost, and
+/// subscribed to again since it forgets the subscription with the session.
+pub fn subscribe(broker: &str, topic: &str) -> Result<BoxStream<'static, Vec<u8>>, MonitorError> {
+    let (host, port) = match broker.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| MonitorError::Argument(format!("invalid --mqtt port '{}'", port)))?,
+        ),
+        None => (broker, DEFAULT_PORT),
+    };
+    let client_id = format!("tui_state_monitor-{}", std::process::id());
+    let mut options = MqttOptions::new(client_id, host, port);
+    options.set_keep_alive(KEEP_ALIVE);
+    let (client, eventloop) = AsyncClient::new(options, REQUEST_CAPACITY);
+    let topic = topic.to_string();
+    let payloads = futures::stream::unfold(
+        (client, eventloop, topic),
+        |(client, mut eventloop, topic)| async move {
+            loop {
+                match eventloop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        if let Err(e) = client.subscribe(topic.as_str(), QoS::AtLeastOnce).await {
+                            r2r::log_error!(
+                                "monitor",
+                                "Could not subscribe to MQTT topic '{}': '{}'.",
+                                topic,
+                                e
+                            );
+                        }
+                    }
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        return Some((publish.payload.to_vec(), (client, eventloop, topic)));
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        r2r::log_warn!("monitor", "Lost the MQTT broker: '{}'.", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                }
+            }
+        },
+    );
+    Ok(payloads.boxed())
+}
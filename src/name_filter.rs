@@ -0,0 +1,68 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// Include/exclude glob patterns on interface names, applied before an update
+/// reaches the `App`. An excluded name is dropped even if it is also included,
+/// and an empty include list includes everything.
+#[derive(Debug, Clone, Default)]
+pub struct NameFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl NameFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<NameFilter, globset::Error> {
+        Ok(NameFilter {
+            include: build_set(include)?,
+            exclude: build_set(exclude)?,
+        })
+    }
+
+    pub fn accepts(&self, name: &str) -> bool {
+        if let Some(exclude) = &self.exclude {
+            if exclude.is_match(name) {
+                return false;
+            }
+        }
+        match &self.include {
+            Some(include) => include.is_match(name),
+            None => true,
+        }
+    }
+}
+
+fn build_set(patterns: &[String]) -> Result<Option<GlobSet>, globset::Error> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // `*` stays within one path segment, `**` spans several.
+        builder.add(GlobBuilder::new(pattern).literal_separator(true).build()?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> NameFilter {
+        let strings = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect::<Vec<_>>();
+        NameFilter::new(&strings(include), &strings(exclude)).unwrap()
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let filter = filter(&["/robot1/*"], &["/robot1/camera"]);
+        assert!(!filter.accepts("/robot1/camera"));
+        assert!(filter.accepts("/robot1/arm"));
+        assert!(!filter.accepts("/robot2/arm"));
+    }
+
+    #[test]
+    fn empty_include_includes_everything() {
+        let filter = filter(&[], &["/robot1/*"]);
+        assert!(filter.accepts("/robot2/arm"));
+        assert!(!filter.accepts("/robot1/arm"));
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use crate::error::MonitorError;
+
+/// UI preferences that survive restarts, stored as JSON in the user's config directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Prefs {
+    pub pinned: BTreeSet<String>,
+    // The columns in the order they were arranged in, by type or group. Columns
+    // not in it follow in their own order.
+    pub column_order: Vec<String>,
+    // Notes on interfaces by name, kept while they are gone.
+    pub notes: BTreeMap<String, String>,
+    // Why the stored preferences could not be read, if they couldn't. They
+    // are then left as they are rather than saved over.
+    #[serde(skip)]
+    invalid: Option<String>,
+}
+
+impl Prefs {
+    /// Loads the stored preferences, falling back to the defaults if there are
+    /// none or they are invalid.
+    pub fn load() -> Prefs {
+        let Some(path) = path() else {
+            return Prefs::default();
+        };
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Prefs::default();
+        };
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            r2r::log_warn!(
+                "monitor",
+                "Ignoring the invalid UI preferences in '{}': '{}'.",
+                path.display(),
+                e
+            );
+            Prefs {
+                invalid: Some(e.to_string()),
+                ..Prefs::default()
+            }
+        })
+    }
+
+    /// Stores the preferences, unless the stored ones were invalid: those are
+    /// kept for the user to fix rather than lost.
+    pub fn save(&self) -> Result<(), MonitorError> {
+        let path = path().ok_or(MonitorError::NoConfigDir)?;
+        if let Some(reason) = &self.invalid {
+            return Err(MonitorError::Invalid {
+                what: "UI preferences",
+                path,
+                reason: reason.clone(),
+            });
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("tui_state_monitor").join("ui.json"))
+}
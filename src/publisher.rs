@@ -1,53 +1,72 @@
 use r2r::QosProfile;
-use serde_json::json;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-// for testing pupposes
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let ctx = r2r::Context::create()?;
-    let mut node = r2r::Node::create(ctx, "testnode", "")?;
-    let duration = std::time::Duration::from_millis(200);
+use crate::app::InterfaceState;
+use crate::cli::PublishTestArgs;
+use crate::error::MonitorError;
+
+pub const INTERFACE_TYPES: [&str; 3] = ["publisher", "subscriber", "server"];
+
+// for testing purposes
+pub async fn run(args: PublishTestArgs) -> Result<(), MonitorError> {
+    if !args.rate.is_finite() || args.rate <= 0.0 {
+        return Err(MonitorError::Argument("--rate must be positive".into()));
+    }
+    if args.interfaces == 0 || args.states.is_empty() {
+        return Err(MonitorError::Argument(
+            "--interfaces and --states must not be empty".into(),
+        ));
+    }
+
+    let mut node = crate::create_node("testnode")?;
+    let duration = Duration::from_secs_f64(1.0 / args.rate);
 
     let mut timer = node.create_wall_timer(duration)?;
-    let publisher = node.create_publisher::<r2r::std_msgs::msg::String>("/monitored_state", QosProfile::default())?;
+    let publisher = node
+        .create_publisher::<r2r::std_msgs::msg::String>(&args.topic, QosProfile::default())
+        .map_err(|source| MonitorError::Publish {
+            topic: args.topic.clone(),
+            source,
+        })?;
 
-    let handle = tokio::task::spawn_blocking(move || loop {
+    let _spin = tokio::task::spawn_blocking(move || loop {
         node.spin_once(std::time::Duration::from_millis(1));
     });
 
-    for _ in 1..=10000 {
+    let seed = args.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    eprintln!("publishing with --seed {}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+    loop {
         timer.tick().await?;
 
-        let interfaces = vec![
-            String::from("publisher"),
-            String::from("subscriber"),
-            String::from("server"),
-        ];
-
-        let activity = vec!(
-            String::from("Active"),
-            String::from("Inactive"),
-        );
-
-        // let number = 0..10;
-    
-        let random_interface = interfaces.choose(&mut rand::thread_rng()).unwrap();
-        let random_activity = activity.choose(&mut rand::thread_rng()).unwrap();
-        let mut rng = rand::thread_rng();
-        let i: i32 = rng.gen_range(0..10);
+        let interface_type = INTERFACE_TYPES.choose(&mut rng).unwrap();
+        let state = args.states.choose(&mut rng).unwrap();
+        let i = rng.gen_range(1..=args.interfaces);
 
+        let interface_state = InterfaceState {
+            name: format!("{} {}", interface_type, i),
+            interface_type: interface_type.to_string(),
+            state: state.clone(),
+            substates: None,
+            message: None,
+            tags: None,
+            raw: None,
+            stamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs_f64()),
+        };
         let msg = r2r::std_msgs::msg::String {
-            data: json!({
-                "name": format!("server {}", i),
-                "interface_type": random_interface,
-                "state": random_activity
-            }).to_string(),
+            data: serde_json::to_string(&interface_state)?,
         };
-        publisher.publish(&msg)?;
+        publisher
+            .publish(&msg)
+            .map_err(|source| MonitorError::Publish {
+                topic: args.topic.clone(),
+                source,
+            })?;
     }
-
-    handle.await?;
-    Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,251 @@
+use futures::FutureExt;
+use r2r::QosProfile;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+use crate::app::App;
+use crate::config::{Banner, Config, Density, SeverityOrder};
+use crate::encoding::Encoding;
+use crate::error::MonitorError;
+use crate::expected::Expected;
+use crate::keymap::Keymap;
+use crate::transform::Transform;
+
+// Editors save in several steps, changes this close together are one edit.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// What the UI takes over from a reloaded config, or why it was rejected,
+/// and how a re-subscription it asked for went.
+pub enum Reload {
+    Applied {
+        flash: Option<Duration>,
+        stale: Option<Duration>,
+        keymap: Keymap,
+        banner: Banner,
+        density: Density,
+        severity: SeverityOrder,
+    },
+    Rejected(String),
+    // Why each topic that could not be subscribed to again failed.
+    Resubscribed(Vec<String>),
+}
+
+/// The seconds of `[ui] flash_seconds` as the UI takes them, 0 turning it off.
+pub fn flash(config: &Config) -> Option<Duration> {
+    Duration::try_from_secs_f64(config.ui.flash_seconds)
+        .ok()
+        .filter(|flash| !flash.is_zero())
+}
+
+/// The seconds of `[ui] stale_seconds` as the UI takes them, 0 turning it off.
+pub fn stale(config: &Config) -> Option<Duration> {
+    Duration::try_from_secs_f64(config.ui.stale_seconds)
+        .ok()
+        .filter(|stale| !stale.is_zero())
+}
+
+/// Applies the `--config` file again whenever it changes, keeping what is
+/// applied when an edit is invalid, and subscribes to every topic again when
+/// the UI asks for it. It owns the subscriptions, so that there is only ever
+/// one per topic.
+pub struct Reloader {
+    // Without a `--config` only re-subscribing is asked for.
+    pub path: Option<PathBuf>,
+    pub config: Config,
+    // The transform of the config, as the subscriptions apply it.
+    pub transform: Option<Arc<Transform>>,
+    pub expected: Expected,
+    // The topics come from the command line with `--compare`, not from the config.
+    pub compare: bool,
+    // Nothing is subscribed to with `--from-graph`.
+    pub from_graph: bool,
+    pub encoding: Encoding,
+    pub arc_node: Arc<Mutex<r2r::Node>>,
+    // Shared with the spin task, which counts the publishers of each.
+    pub topics: Arc<RwLock<Vec<String>>>,
+    pub shared_apps: Vec<Arc<RwLock<App>>>,
+    // The subscriber task of each topic, `None` where subscribing failed.
+    pub subscriptions: Vec<Option<JoinHandle<()>>>,
+    pub ui: UnboundedSender<Reload>,
+}
+
+// Aborting the reloading task unsubscribes from what it subscribed to.
+impl Drop for Reloader {
+    fn drop(&mut self) {
+        for subscription in self.subscriptions.iter().flatten() {
+            subscription.abort();
+        }
+    }
+}
+
+impl Reloader {
+    pub async fn run(mut self, mut resubscribe: UnboundedReceiver<()>) {
+        let watching = self.path.as_deref().and_then(|path| {
+            crate::config::watch(path)
+                .map_err(|e| {
+                    r2r::log_warn!(
+                        "monitor",
+                        "Could not watch the config for changes: '{}'.",
+                        e
+                    )
+                })
+                .ok()
+        });
+        let (_watcher, mut changes) = watching.unzip();
+        loop {
+            tokio::select! {
+                Some(()) = next_change(&mut changes) => {
+                    tokio::time::sleep(DEBOUNCE).await;
+                    while next_change(&mut changes).now_or_never().is_some() {}
+                    let reload = match self.reload().await {
+                        Ok(keymap) => Reload::Applied {
+                            flash: flash(&self.config),
+                            stale: stale(&self.config),
+                            keymap,
+                            banner: self.config.banner.clone(),
+                            density: self.config.ui.density,
+                            severity: self.config.severity.clone(),
+                        },
+                        Err(e) => {
+                            r2r::log_warn!("monitor", "Kept the previous config, {}.", e);
+                            Reload::Rejected(e)
+                        }
+                    };
+                    // Headless there is no UI to tell.
+                    let _ = self.ui.send(reload);
+                }
+                Some(()) = resubscribe.recv() => {
+                    let failed = self.resubscribe().await;
+                    let _ = self.ui.send(Reload::Resubscribed(failed));
+                }
+                // Headless nothing asks for re-subscribing, the subscriptions
+                // still live until the task is aborted.
+                else => std::future::pending::<()>().await,
+            }
+        }
+    }
+
+    /// Loads and checks the whole config before applying any of it. The
+    /// state values and limits apply to the interfaces right away, a changed
+    /// topic, QoS or transform re-subscribes.
+    async fn reload(&mut self) -> Result<Keymap, String> {
+        let Some(path) = &self.path else {
+            return Err("there is no config to reload".to_string());
+        };
+        let mut config = Config::load(path).map_err(|e| e.to_string())?;
+        let keymap =
+            Keymap::new(&config.keys).map_err(|e| format!("invalid config: keys: {}", e))?;
+        let transform = config
+            .transform
+            .as_deref()
+            .map(Transform::new)
+            .transpose()
+            .map_err(|e| format!("invalid config: transform: {}", e))?
+            .map(Arc::new);
+        if let Some(topic) = &self.expected.topic {
+            config.topic = topic.clone();
+        }
+
+        for shared_app in &self.shared_apps {
+            shared_app
+                .write()
+                .unwrap()
+                .reconfigure(config.states.clone(), config.limits.clone());
+        }
+        let transform_changed = config.transform != self.config.transform;
+        self.transform = transform;
+        for i in 0..self.shared_apps.len() {
+            let old_topic = self.topics.read().unwrap()[i].clone();
+            let topic = if self.compare {
+                old_topic.clone()
+            } else {
+                config.topic.clone()
+            };
+            let qos = config.qos(&topic);
+            let qos_changed = qos != self.config.qos(&old_topic);
+            if self.from_graph || (topic == old_topic && !transform_changed && !qos_changed) {
+                continue;
+            }
+            let shared_app = &self.shared_apps[i];
+            if topic != old_topic {
+                // What the old topic reported says nothing about the new one.
+                let mut app = shared_app.write().unwrap();
+                app.clear();
+                for interface in &self.expected.interfaces {
+                    app.expect(interface.interface_type.as_str(), &interface.name);
+                }
+                self.topics.write().unwrap()[i] = topic.clone();
+            }
+            // A failure shows in the footer of the topic.
+            let _ = self.subscribe(i, &topic, qos).await;
+        }
+        self.config = config;
+        Ok(keymap)
+    }
+
+    /// Subscribes to every topic again, for when a subscription seems stuck,
+    /// e.g. after the publishing node restarted. Tells which topics failed.
+    async fn resubscribe(&mut self) -> Vec<String> {
+        let mut failed = Vec::new();
+        if self.from_graph {
+            return failed;
+        }
+        for i in 0..self.shared_apps.len() {
+            let topic = self.topics.read().unwrap()[i].clone();
+            let qos = self.config.qos(&topic);
+            if let Err(e) = self.subscribe(i, &topic, qos).await {
+                failed.push(e.to_string());
+            }
+        }
+        failed
+    }
+
+    /// Replaces the subscription of the `i`th topic by a new one, keeping
+    /// what its app already holds.
+    async fn subscribe(
+        &mut self,
+        i: usize,
+        topic: &str,
+        qos: QosProfile,
+    ) -> Result<(), MonitorError> {
+        if let Some(subscription) = self.subscriptions[i].take() {
+            subscription.abort();
+        }
+        let shared_app = &self.shared_apps[i];
+        match crate::spawn_subscriber(
+            self.arc_node.clone(),
+            topic,
+            qos,
+            self.encoding,
+            self.transform.clone(),
+            shared_app,
+        )
+        .await
+        {
+            Ok(subscription) => {
+                self.subscriptions[i] = Some(subscription);
+                shared_app.write().unwrap().clear_subscription_error();
+                Ok(())
+            }
+            Err(e) => {
+                r2r::log_error!("monitor", "Subscribing failed, {}.", e);
+                shared_app
+                    .write()
+                    .unwrap()
+                    .set_subscription_error(e.to_string());
+                Err(e)
+            }
+        }
+    }
+}
+
+/// The next change of the watched config, never without one.
+async fn next_change(changes: &mut Option<UnboundedReceiver<()>>) -> Option<()> {
+    match changes {
+        Some(changes) => changes.recv().await,
+        None => std::future::pending().await,
+    }
+}
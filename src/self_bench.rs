@@ -0,0 +1,84 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::time::Instant;
+
+use crate::app::{App, InterfaceState};
+use crate::cli::SelfBenchArgs;
+use crate::config::{Limits, StateValues};
+use crate::error::MonitorError;
+use crate::name_filter::NameFilter;
+use crate::publisher::INTERFACE_TYPES;
+
+/// Runs a flood of made-up states through `App::update_state`, without ROS
+/// or the UI, and prints the throughput and the memory used. The states are
+/// generated up front, so only the update path is timed.
+pub fn run(args: SelfBenchArgs) -> Result<(), MonitorError> {
+    if args.updates == 0 || args.interfaces == 0 || args.states.is_empty() {
+        return Err(MonitorError::Argument(
+            "--updates, --interfaces and --states must not be empty".into(),
+        ));
+    }
+    let seed = args.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let updates: Vec<InterfaceState> = (0..args.updates)
+        .map(|_| {
+            let interface_type = INTERFACE_TYPES.choose(&mut rng).unwrap();
+            InterfaceState {
+                name: format!("{} {}", interface_type, rng.gen_range(1..=args.interfaces)),
+                interface_type: interface_type.to_string(),
+                state: args.states.choose(&mut rng).unwrap().clone(),
+                stamp: None,
+                substates: None,
+                message: None,
+                tags: None,
+                raw: None,
+            }
+        })
+        .collect();
+
+    let mut app = App::new(
+        NameFilter::default(),
+        StateValues::default(),
+        Limits::default(),
+    );
+    let started = Instant::now();
+    for update in updates {
+        app = app.update_state(update);
+    }
+    let elapsed = started.elapsed();
+
+    println!("seed: {}", seed);
+    println!(
+        "{} updates of {} interfaces in {:.3}s: {:.0} updates/s",
+        args.updates,
+        app.stats.interfaces_seen,
+        elapsed.as_secs_f64(),
+        args.updates as f64 / elapsed.as_secs_f64()
+    );
+    // Only Linux tells the memory of a process this simply. The peak
+    // includes the states generated up front.
+    match (memory_kib("VmRSS"), memory_kib("VmHWM")) {
+        (Some(resident), Some(peak)) => {
+            println!(
+                "memory: {} KiB resident, {} KiB at the peak",
+                resident, peak
+            )
+        }
+        _ => println!("memory: unknown on this platform"),
+    }
+    Ok(())
+}
+
+/// A field of `/proc/self/status`, in KiB.
+fn memory_kib(field: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,164 @@
+use ratatui::style::Color;
+use std::path::Path;
+
+use crate::error::MonitorError;
+
+// The slots of a base16 scheme the monitor draws with, what it uses each
+// for, and the color it stands in for.
+const SLOTS: [(&str, &str, Color); 11] = [
+    ("base00", "text on colored cells", Color::Black),
+    ("base03", "dimmed text", Color::DarkGray),
+    ("base04", "unknown states", Color::Gray),
+    ("base05", "text and borders", Color::Reset),
+    ("base07", "text on the error banner", Color::White),
+    ("base08", "errors and inactive states", Color::Red),
+    ("base0A", "warnings", Color::Yellow),
+    ("base0B", "active states", Color::Green),
+    ("base0C", "numbers", Color::Cyan),
+    ("base0D", "idle states", Color::Blue),
+    ("base0E", "literals", Color::Magenta),
+];
+
+// The slot the selected row is highlighted with, instead of reversing it.
+const SELECTION: &str = "base02";
+
+/// The colors of a `--base16` scheme, in place of the terminal's named
+/// colors the monitor draws with by default.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    // Each named color with the color of the scheme it is drawn in.
+    colors: Vec<(Color, Color)>,
+    selection: Option<Color>,
+}
+
+impl Theme {
+    /// Reads a base16 scheme, either with the slots at the top level or, as
+    /// newer schemes have them, under `palette`. A slot the scheme doesn't
+    /// set keeps its default color, with a warning.
+    pub fn load_base16(path: &Path) -> Result<Theme, MonitorError> {
+        let text = std::fs::read_to_string(path).map_err(|source| MonitorError::Read {
+            what: "base16 scheme",
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Theme::parse_base16(&text, path)
+    }
+
+    /// Parses a base16 scheme read from `path`.
+    fn parse_base16(text: &str, path: &Path) -> Result<Theme, MonitorError> {
+        let invalid = |reason: String| MonitorError::Invalid {
+            what: "base16 scheme",
+            path: path.to_path_buf(),
+            reason,
+        };
+        let scheme: serde_json::Value =
+            serde_yaml::from_str(text).map_err(|e| invalid(e.to_string()))?;
+        let palette = scheme.get("palette").unwrap_or(&scheme);
+        let Some(palette) = palette.as_object() else {
+            return Err(invalid("not a map of slots".to_string()));
+        };
+
+        let slot = |slot: &str, purpose: &str| {
+            // Schemes differ in the case of the hex digit in the slot name.
+            let value = palette
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(slot))
+                .map(|(_, value)| value);
+            let Some(value) = value else {
+                r2r::log_warn!(
+                    "monitor",
+                    "The base16 scheme has no {}, using the default color for {}.",
+                    slot,
+                    purpose
+                );
+                return Ok(None);
+            };
+            value
+                .as_str()
+                .and_then(parse_hex)
+                .map(Some)
+                .ok_or_else(|| invalid(format!("{}: {} is not a hex color", slot, value)))
+        };
+        let mut theme = Theme {
+            colors: Vec::new(),
+            selection: slot(SELECTION, "the selection")?,
+        };
+        for (name, purpose, default) in SLOTS {
+            if let Some(color) = slot(name, purpose)? {
+                theme.colors.push((default, color));
+            }
+        }
+        Ok(theme)
+    }
+
+    /// The color `color` is drawn in, the terminal's default background
+    /// staying what it is.
+    pub fn foreground(&self, color: Color) -> Color {
+        self.lookup(color)
+    }
+
+    pub fn background(&self, color: Color) -> Color {
+        match color {
+            Color::Reset => Color::Reset,
+            color => self.lookup(color),
+        }
+    }
+
+    /// The background of the selected row, `None` to reverse it instead.
+    pub fn selection(&self) -> Option<Color> {
+        self.selection
+    }
+
+    fn lookup(&self, color: Color) -> Color {
+        self.colors
+            .iter()
+            .find(|(named, _)| *named == color)
+            .map_or(color, |(_, themed)| *themed)
+    }
+}
+
+/// Parses a color written as six hex digits, with or without a leading `#`.
+fn parse_hex(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_need_six_digits() {
+        assert_eq!(parse_hex("#ABCDEF"), Some(Color::Rgb(0xab, 0xcd, 0xef)));
+        assert_eq!(parse_hex("abcdef"), Some(Color::Rgb(0xab, 0xcd, 0xef)));
+        for text in ["#ABC", "#ABCDE", "#ABCDEF0", "#GHIJKL", ""] {
+            assert_eq!(parse_hex(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn slots_are_found_in_either_case_under_the_palette() {
+        let scheme = r##"{"palette": {"base0a": "#ABCDEF", "base0B": "123456"}}"##;
+        let theme = Theme::parse_base16(scheme, Path::new("scheme.yaml")).unwrap();
+        assert_eq!(
+            theme.foreground(Color::Yellow),
+            Color::Rgb(0xab, 0xcd, 0xef)
+        );
+        assert_eq!(theme.foreground(Color::Green), Color::Rgb(0x12, 0x34, 0x56));
+        // Slots the scheme leaves out keep their default colors.
+        assert_eq!(theme.foreground(Color::Red), Color::Red);
+        assert_eq!(theme.background(Color::Reset), Color::Reset);
+        assert_eq!(theme.selection(), None);
+    }
+
+    #[test]
+    fn a_short_hex_color_makes_the_scheme_invalid() {
+        let scheme = r##"{"base08": "#ABC"}"##;
+        let error = Theme::parse_base16(scheme, Path::new("scheme.yaml")).unwrap_err();
+        assert!(matches!(error, MonitorError::Invalid { .. }), "{}", error);
+    }
+}
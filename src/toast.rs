@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// How long each toast is shown before the next queued one.
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+// Toasts beyond this many waiting are dropped, oldest first.
+const MAX_QUEUED: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub text: String,
+    pub severity: Severity,
+}
+
+/// Short-lived feedback shown in the footer one at a time, so that messages
+/// from actions in quick succession are queued instead of overwriting each other.
+#[derive(Debug, Clone, Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+    // When the toast at the front of the queue was first shown.
+    shown_since: Option<Instant>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: impl Into<String>, severity: Severity) {
+        if self.queue.len() == MAX_QUEUED {
+            // Keep the one being shown, drop the oldest waiting one.
+            self.queue.remove(1);
+        }
+        self.queue.push_back(Toast {
+            text: text.into(),
+            severity,
+        });
+    }
+
+    /// Moves on to the next toast once the current one has been shown long
+    /// enough. Returns whether the shown toast changed.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        match self.shown_since {
+            Some(since) if now.saturating_duration_since(since) >= TOAST_DURATION => {
+                self.queue.pop_front();
+                self.shown_since = (!self.queue.is_empty()).then_some(now);
+                true
+            }
+            None if !self.queue.is_empty() => {
+                self.shown_since = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn current(&self) -> Option<&Toast> {
+        self.shown_since.and(self.queue.front())
+    }
+}
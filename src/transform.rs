@@ -0,0 +1,50 @@
+use jaq_interpret::{Ctx, Filter, FilterT, ParseCtx, RcIter, Val};
+
+/// A jq expression that reshapes every message into states before they are
+/// parsed, for publishers whose messages don't have the expected shape.
+pub struct Transform {
+    filter: Filter,
+}
+
+impl Transform {
+    /// Compiles the expression with the jq standard library, e.g.
+    /// `.items[] | {name: .id, interface_type: "server", state: .status}`.
+    pub fn new(expression: &str) -> Result<Transform, String> {
+        let mut defs = ParseCtx::new(Vec::new());
+        defs.insert_natives(jaq_core::core());
+        defs.insert_defs(jaq_std::std());
+        let (parsed, errors) = jaq_parse::parse(expression, jaq_parse::main());
+        if let Some(error) = errors.first() {
+            return Err(format!(
+                "'{}' is not a jq expression: {}",
+                expression, error
+            ));
+        }
+        let filter = defs.compile(parsed.ok_or("empty jq expression")?);
+        if let Some((error, _)) = defs.errs.first() {
+            return Err(format!(
+                "'{}' is not a jq expression: {}",
+                expression, error
+            ));
+        }
+        Ok(Transform { filter })
+    }
+
+    /// Applies the expression to a message, giving the JSON to parse states
+    /// from: what the expression outputs, an array if it outputs several values.
+    pub fn apply(&self, input: serde_json::Value) -> Result<String, String> {
+        let inputs = RcIter::new(core::iter::empty());
+        let mut outputs = self
+            .filter
+            .run((Ctx::new([], &inputs), Val::from(input)))
+            .map(|output| output.map(serde_json::Value::from))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("the transform failed: {}", e))?;
+        let output = match outputs.len() {
+            0 => return Err("the transform output nothing".to_string()),
+            1 => outputs.remove(0),
+            _ => serde_json::Value::Array(outputs),
+        };
+        Ok(output.to_string())
+    }
+}
@@ -0,0 +1,3404 @@
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::io::{self, Stdout};
+use std::ops::ControlFlow;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, Once, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use crossterm::cursor::Show;
+use crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event as CEvent, EventStream, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use futures::StreamExt;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, Borders, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState, Widget,
+};
+use ratatui::{Frame, Terminal, TerminalOptions, Viewport};
+use regex::Regex;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::time::MissedTickBehavior;
+
+use crate::app::{App, Health, Interface, State, Transition, Verdict};
+use crate::config::{Banner, Density, SeverityOrder};
+use crate::error::MonitorError;
+use crate::history::{self, DistributionChart, History};
+use crate::keymap::{Action, Command, Keymap};
+use crate::link::{self, Pace};
+use crate::prefs::Prefs;
+use crate::reload::Reload;
+use crate::theme::Theme;
+use crate::toast::{Severity, Toasts};
+use crate::zone::Zone;
+
+/// UI-only state of the monitor, independent of the monitored interfaces.
+#[derive(Debug, Clone, Default)]
+struct View {
+    filter: String,
+    editing_filter: bool,
+    // The interface a note is being written for, and the note so far.
+    note: Option<(String, String)>,
+    // Match the filter as a plain substring instead of fuzzily.
+    substring_filter: bool,
+    show_help: bool,
+    // The checklist of the interfaces against the expected ones.
+    show_report: bool,
+    show_history: bool,
+    // Show each run of transitions of one interface as a single line.
+    collapse_runs: bool,
+    // Without colors, states are told apart by a tag in front of the name.
+    colors: bool,
+    // The colors of a `--base16` scheme, in place of the named ones.
+    theme: Option<Theme>,
+    flash: Option<Duration>,
+    // How long an interface may go without an update before it counts as
+    // stale, never with `None`.
+    stale: Option<Duration>,
+    banner: Banner,
+    density: Density,
+    // Which interface the colors of a column, group or minimap cell show.
+    severity: SeverityOrder,
+    // Index of the focused column and the selection within its visible rows.
+    focus: usize,
+    list_state: ListState,
+    prefs: Prefs,
+    // Acknowledged interfaces, for this session only. Muting is what this
+    // screen shows alone: the totals, the history and the notices leave
+    // them out, while `/healthz`, the diagnostics and D-Bus, which other
+    // people and tools rely on, still count every interface.
+    // The names by type, so that a publisher and a server of one name are
+    // muted apart. A type without muted interfaces has no entry.
+    muted: BTreeMap<&'static str, BTreeSet<String>>,
+    history: History,
+    group_by: Option<Regex>,
+    // Header rows within the columns by what this captures from the names.
+    subgroup_by: Option<Regex>,
+    // Collapsed subgroups by column and group, for this session only.
+    collapsed: BTreeSet<(String, String)>,
+    // List the active interfaces of a column as only a count, unless the
+    // column is in `expanded_active`.
+    collapse_active: bool,
+    expanded_active: BTreeSet<String>,
+    // Where the last frame drew each column's overview bar, for mouse clicks.
+    minimaps: Vec<Minimap>,
+    toasts: Toasts,
+    // The compared topics, one pane each, empty when monitoring a single topic.
+    topics: Vec<String>,
+    keymap: Keymap,
+    // Show aliases instead of interface names.
+    redact: bool,
+    // The alias number of each name by type, handed out as the names are
+    // first shown, for this session only.
+    aliases: Arc<Mutex<HashMap<(String, String), usize>>>,
+    // The view frozen by `--pause-on-error`, shown instead of the live one.
+    paused: Option<Pause>,
+    zone: Zone,
+    // Show each column as a grid of cells instead of a list.
+    grid: bool,
+    // Show the interfaces of every type in a single list, sorted by `sort`.
+    unified: bool,
+    sort: Sort,
+    // Open while searching for an action to run.
+    palette: Option<Palette>,
+    // How many cells a row of the focused column's grid held in the last frame.
+    grid_width: usize,
+    // Select each interface as it changes, unless there was input lately.
+    follow: bool,
+    last_input: Option<Instant>,
+    // List every key binding in the footer.
+    expanded_help: bool,
+    // Draw nothing but ASCII.
+    ascii: bool,
+    // Show the last payload of the selected interface in the detail pane.
+    show_payload: bool,
+    // Asks for subscribing to the topics again, `None` with nothing subscribed.
+    resubscribe: Option<UnboundedSender<()>>,
+}
+
+// How long after the last key or click `--follow` moves the selection again.
+const FOLLOW_RESUME: Duration = Duration::from_secs(10);
+
+/// What the unified list is sorted by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Sort {
+    #[default]
+    Name,
+    Type,
+    // Worst first.
+    State,
+    // Longest without an update first.
+    Age,
+}
+
+impl Sort {
+    fn next(self) -> Sort {
+        match self {
+            Sort::Name => Sort::Type,
+            Sort::Type => Sort::State,
+            Sort::State => Sort::Age,
+            Sort::Age => Sort::Name,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Sort::Name => "name",
+            Sort::Type => "type",
+            Sort::State => "state",
+            Sort::Age => "age",
+        }
+    }
+}
+
+/// The command palette: every action, narrowed down by what is typed.
+#[derive(Debug, Clone, Default)]
+struct Palette {
+    query: String,
+    // Index among the matching commands.
+    selected: usize,
+}
+
+impl Palette {
+    /// The commands matching the query, best first, or all of them in order.
+    fn matches(&self, keymap: &Keymap) -> Vec<Command> {
+        let commands = keymap.commands();
+        if self.query.is_empty() {
+            return commands;
+        }
+        let matcher = SkimMatcherV2::default().ignore_case();
+        let mut scored: Vec<(i64, Command)> = commands
+            .into_iter()
+            .filter_map(|command| {
+                let text = format!("{} {}", command.name, command.description);
+                matcher
+                    .fuzzy_match(&text, &self.query)
+                    .map(|score| (score, command))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| Reverse(*score));
+        scored.into_iter().map(|(_, command)| command).collect()
+    }
+}
+
+// The column of all interfaces in the unified list.
+const UNIFIED: &str = "all";
+
+/// What the view looked like when an interface went to Error, and which one.
+#[derive(Debug, Clone)]
+struct Pause {
+    apps: Arc<Vec<App>>,
+    at: Instant,
+    reason: String,
+}
+
+/// The overview bar of a column, one cell per interface or, when there are
+/// more interfaces than cells, per run of neighbouring interfaces.
+#[derive(Debug, Clone, Copy)]
+struct Minimap {
+    area: Rect,
+    column: usize,
+    rows: usize,
+}
+
+impl Minimap {
+    /// The first of the visible rows that the cell at `x` stands for.
+    fn row_at(&self, x: u16) -> Option<usize> {
+        let cell = x.checked_sub(self.area.x)? as usize;
+        let cells = self.rows.min(self.area.width as usize);
+        (cell < cells).then(|| cell * self.rows / cells)
+    }
+}
+
+/// One column of interfaces, in name order.
+struct Column<'a> {
+    title: String,
+    // The type or group the column is for, unlike the title never redacted.
+    key: &'a str,
+    rows: Vec<(&'a String, &'a Interface)>,
+    // Which of the compared topics the column belongs to.
+    pane: usize,
+    // Interfaces whose state another compared topic reports differently.
+    differing: BTreeSet<&'a String>,
+}
+
+// The column of interfaces whose name the `--group-by` expression doesn't
+// match, and the subgroup of those `--subgroup-by` doesn't.
+const UNGROUPED: &str = "other";
+
+/// A line of a column's list: an interface or, with `--subgroup-by`, the
+/// header of the interfaces listed after it.
+#[derive(Debug, Clone, Copy)]
+enum Entry<'a> {
+    Interface(&'a String, &'a Interface),
+    Header(Subgroup<'a>),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Subgroup<'a> {
+    key: &'a str,
+    count: usize,
+    // The worst health of its unmuted interfaces, for the header's color.
+    worst: Option<Health>,
+    collapsed: bool,
+    // The count line of the active interfaces, rather than a subgroup.
+    active: bool,
+}
+
+/// The part of a name that a `--group-by` or `--subgroup-by` expression
+/// captures, its first capture group or else its whole match.
+fn group_key<'a>(expression: &Regex, name: &'a str) -> Option<&'a str> {
+    expression.captures(name).and_then(|captures| {
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|m| m.as_str())
+    })
+}
+
+impl View {
+    fn is_muted(&self, interface_type: &str, name: &str) -> bool {
+        self.muted
+            .get(interface_type)
+            .is_some_and(|names| names.contains(name))
+    }
+
+    fn muted_count(&self) -> usize {
+        self.muted.values().map(BTreeSet::len).sum()
+    }
+
+    /// The name of an interface as it is shown.
+    fn name<'a>(&self, interface_type: &str, name: &'a str) -> Cow<'a, str> {
+        if self.redact {
+            Cow::Owned(self.alias(interface_type, name))
+        } else {
+            Cow::Borrowed(name)
+        }
+    }
+
+    /// An alias for a name, e.g. "server-#4": numbered per type in the order
+    /// the names are first shown, so that the same name keeps its alias for
+    /// the session and no two names share one, without the alias telling
+    /// anything about the name.
+    fn alias(&self, kind: &str, name: &str) -> String {
+        let mut aliases = self.aliases.lock().unwrap();
+        let key = (kind.to_string(), name.to_string());
+        let number = match aliases.get(&key) {
+            Some(&number) => number,
+            None => {
+                let number = aliases.keys().filter(|(other, _)| other == kind).count() + 1;
+                aliases.insert(key, number);
+                number
+            }
+        };
+        format!("{}-#{}", kind, number)
+    }
+
+    /// How well an interface matches the filter, higher is better, `None` if
+    /// it doesn't. A filter starting with `#` matches the interface's tags
+    /// instead of its name, the best matching tag counts.
+    fn score(&self, matcher: &SkimMatcherV2, name: &str, interface: &Interface) -> Option<i64> {
+        let matches = |text: &str, filter: &str| {
+            if filter.is_empty() {
+                Some(0)
+            } else if self.substring_filter {
+                text.to_lowercase()
+                    .contains(&filter.to_lowercase())
+                    .then_some(0)
+            } else {
+                matcher.fuzzy_match(text, filter)
+            }
+        };
+        match self.filter.strip_prefix('#') {
+            Some(tag) => interface
+                .tags
+                .iter()
+                .filter_map(|other| matches(other, tag))
+                .max(),
+            None => matches(name, &self.filter),
+        }
+    }
+
+    /// The rows of a column that pass the filter, pinned interfaces first and
+    /// then the best matches.
+    fn visible<'a>(&self, column: &Column<'a>) -> Vec<(&'a String, &'a Interface)> {
+        let matcher = SkimMatcherV2::default().ignore_case();
+        let mut rows: Vec<_> = column
+            .rows
+            .iter()
+            .filter_map(|&(name, interface)| {
+                self.score(&matcher, name, interface)
+                    .map(|score| (score, name, interface))
+            })
+            .collect();
+        rows.sort_by_key(|(score, name, _)| (!self.prefs.pinned.contains(*name), Reverse(*score)));
+        rows.into_iter()
+            .map(|(_, name, interface)| (name, interface))
+            .collect()
+    }
+
+    /// The lines of a column's list: its visible rows, those after the pinned
+    /// ones under a header per subgroup unless the subgroup is collapsed.
+    /// With `collapse_active` the active ones follow last, under a count line.
+    /// The grid and the single list have no headers.
+    fn entries<'a>(&self, column: &Column<'a>) -> Vec<Entry<'a>> {
+        self.entries_of(column.key, &self.visible(column))
+    }
+
+    fn entries_of<'a>(
+        &self,
+        column_key: &str,
+        rows: &[(&'a String, &'a Interface)],
+    ) -> Vec<Entry<'a>> {
+        let as_entries = |rows: &[(&'a String, &'a Interface)]| {
+            rows.iter()
+                .map(|&(name, interface)| Entry::Interface(name, interface))
+                .collect::<Vec<_>>()
+        };
+        if self.grid || self.unified || (self.subgroup_by.is_none() && !self.collapse_active) {
+            return as_entries(rows);
+        }
+        let pinned = rows
+            .iter()
+            .take_while(|(name, _)| self.prefs.pinned.contains(*name))
+            .count();
+        let mut entries = as_entries(&rows[..pinned]);
+        let (active, others): (Vec<_>, Vec<_>) = rows[pinned..]
+            .iter()
+            .partition(|(_, interface)| self.collapse_active && interface.health() == Health::Ok);
+        self.push_subgroups(column_key, &others, &mut entries);
+        if !active.is_empty() {
+            let collapsed = !self.expanded_active.contains(column_key);
+            entries.push(Entry::Header(Subgroup {
+                key: "active",
+                count: active.len(),
+                worst: Some(Health::Ok),
+                collapsed,
+                active: true,
+            }));
+            if !collapsed {
+                entries.extend(as_entries(&active));
+            }
+        }
+        entries
+    }
+
+    /// Appends the rows under a header per subgroup, or as they are without
+    /// `--subgroup-by`.
+    fn push_subgroups<'a>(
+        &self,
+        column_key: &str,
+        rows: &[(&'a String, &'a Interface)],
+        entries: &mut Vec<Entry<'a>>,
+    ) {
+        let as_entries = |rows: &[(&'a String, &'a Interface)]| {
+            rows.iter()
+                .map(|&(name, interface)| Entry::Interface(name, interface))
+                .collect::<Vec<_>>()
+        };
+        let Some(subgroup_by) = &self.subgroup_by else {
+            entries.extend(as_entries(rows));
+            return;
+        };
+        let mut groups: BTreeMap<&str, Vec<(&String, &Interface)>> = BTreeMap::new();
+        let mut ungrouped = Vec::new();
+        for &(name, interface) in rows {
+            match group_key(subgroup_by, name) {
+                Some(key) => groups.entry(key).or_default().push((name, interface)),
+                None => ungrouped.push((name, interface)),
+            }
+        }
+        let ungrouped = (!ungrouped.is_empty()).then_some((UNGROUPED, ungrouped));
+        for (key, members) in groups.into_iter().chain(ungrouped) {
+            let collapsed = self
+                .collapsed
+                .contains(&(column_key.to_string(), key.to_string()));
+            let worst = members
+                .iter()
+                .filter(|(name, interface)| !self.is_muted(interface.interface_type, name))
+                .max_by_key(|(_, interface)| interface.severity(&self.severity))
+                .map(|(_, interface)| interface.health());
+            entries.push(Entry::Header(Subgroup {
+                key,
+                count: members.len(),
+                worst,
+                collapsed,
+                active: false,
+            }));
+            if !collapsed {
+                entries.extend(as_entries(&members));
+            }
+        }
+    }
+
+    /// The columns to show, those of each compared topic in its own pane.
+    fn columns<'a>(&self, apps: &'a [App]) -> Vec<Column<'a>> {
+        let mut columns = Vec::new();
+        for (pane, app) in apps.iter().enumerate() {
+            for mut column in self.columns_of(app, pane) {
+                if apps.len() > 1 {
+                    column.differing = column
+                        .rows
+                        .iter()
+                        .filter(|(name, interface)| {
+                            let others = apps.iter().enumerate().filter(|(i, _)| *i != pane);
+                            others
+                                .filter_map(|(_, other)| other.interface(name))
+                                .any(|other| {
+                                    interface.reported
+                                        && other.reported
+                                        && other.value != interface.value
+                                })
+                        })
+                        .map(|(name, _)| *name)
+                        .collect();
+                }
+                columns.push(column);
+            }
+        }
+        columns
+    }
+
+    /// The columns of one topic: one per interface type or, with `--group-by`,
+    /// one per distinct part of the names that the expression captures.
+    fn columns_of<'a>(&self, app: &'a App, pane: usize) -> Vec<Column<'a>> {
+        let mut columns = self.unordered_columns_of(app, pane);
+        let order = &self.prefs.column_order;
+        columns.sort_by_key(|column| {
+            order
+                .iter()
+                .position(|key| key == column.key)
+                .unwrap_or(usize::MAX)
+        });
+        columns
+    }
+
+    /// The columns of one topic, before arranging them in the stored order.
+    fn unordered_columns_of<'a>(&self, app: &'a App, pane: usize) -> Vec<Column<'a>> {
+        if self.unified {
+            let mut rows: Vec<(&String, &Interface)> = app
+                .columns()
+                .into_iter()
+                .flat_map(|(_, states)| states)
+                .collect();
+            match self.sort {
+                Sort::Name => rows.sort_by_key(|(name, _)| *name),
+                Sort::Type => rows.sort_by_key(|(name, i)| (i.interface_type, *name)),
+                Sort::State => {
+                    rows.sort_by_key(|(name, i)| (Reverse(i.severity(&self.severity)), *name))
+                }
+                Sort::Age => rows.sort_by_key(|(name, i)| (i.updated, *name)),
+            }
+            return vec![Column {
+                title: format!("All interfaces, by {}", self.sort.name()),
+                key: UNIFIED,
+                rows,
+                pane,
+                differing: BTreeSet::new(),
+            }];
+        }
+        let Some(group_by) = &self.group_by else {
+            return app
+                .columns()
+                .into_iter()
+                .map(|(title, states)| Column {
+                    title: title.to_string(),
+                    key: title,
+                    rows: states.iter().collect(),
+                    pane,
+                    differing: BTreeSet::new(),
+                })
+                .collect();
+        };
+        let mut groups: BTreeMap<&str, Vec<(&String, &Interface)>> = BTreeMap::new();
+        let mut ungrouped = Vec::new();
+        for (_, states) in app.columns() {
+            for (name, interface) in states {
+                match group_key(group_by, name) {
+                    Some(key) => groups.entry(key).or_default().push((name, interface)),
+                    None => ungrouped.push((name, interface)),
+                }
+            }
+        }
+        let mut columns: Vec<Column> = groups
+            .into_iter()
+            .map(|(key, rows)| Column {
+                title: self.name("group", key).into_owned(),
+                key,
+                rows,
+                pane,
+                differing: BTreeSet::new(),
+            })
+            .collect();
+        if !ungrouped.is_empty() {
+            columns.push(Column {
+                title: UNGROUPED.to_string(),
+                key: UNGROUPED,
+                rows: ungrouped,
+                pane,
+                differing: BTreeSet::new(),
+            });
+        }
+        for column in &mut columns {
+            column.rows.sort_by_key(|(name, _)| *name);
+        }
+        columns
+    }
+
+    /// The focused column, `None` while there are no columns.
+    fn focused<'a>(&self, apps: &'a [App]) -> Option<Column<'a>> {
+        self.columns(apps).into_iter().nth(self.focus)
+    }
+
+    fn start_note(&mut self, apps: &[App]) {
+        if let Some((name, _)) = self.selected(apps) {
+            let note = self.prefs.notes.get(name).cloned().unwrap_or_default();
+            self.note = Some((name.clone(), note));
+        }
+    }
+
+    /// Stores the note being written, an empty one removing it.
+    fn save_note(&mut self) {
+        let Some((name, note)) = self.note.take() else {
+            return;
+        };
+        let note = note.trim();
+        if note.is_empty() {
+            self.prefs.notes.remove(&name);
+        } else {
+            self.prefs.notes.insert(name, note.to_string());
+        }
+        if let Err(e) = self.prefs.save() {
+            r2r::log_warn!("monitor", "Could not save UI preferences: '{}'.", e);
+            self.toasts
+                .push(format!("could not save the note: {}", e), Severity::Error);
+        }
+    }
+
+    fn toggle_pin(&mut self, apps: &[App]) {
+        if let Some((name, interface)) = self.selected(apps) {
+            let shown = self.name(interface.interface_type, name);
+            if self.prefs.pinned.remove(name) {
+                self.toasts
+                    .push(format!("unpinned {}", shown), Severity::Info);
+            } else {
+                self.prefs.pinned.insert(name.clone());
+                self.toasts
+                    .push(format!("pinned {}", shown), Severity::Info);
+            }
+            if let Err(e) = self.prefs.save() {
+                r2r::log_warn!("monitor", "Could not save UI preferences: '{}'.", e);
+                self.toasts
+                    .push(format!("could not save pins: {}", e), Severity::Error);
+            }
+            // Keep the same interface selected now that it moved.
+            let entries = self.focused(apps).map_or(Vec::new(), |c| self.entries(&c));
+            let index = entries
+                .iter()
+                .position(|entry| matches!(entry, Entry::Interface(other, _) if *other == name));
+            self.list_state.select(index);
+        } else {
+            self.toasts.push("no interface selected", Severity::Warn);
+        }
+    }
+
+    /// Mutes every interface that is unhealthy right now, acknowledging a
+    /// known bad phase, so that those failing from then on stand out.
+    fn mute_problems(&mut self, apps: &[App]) {
+        let problems: Vec<(&'static str, String)> = apps
+            .iter()
+            .flat_map(|app| app.columns())
+            .flat_map(|(_, interfaces)| interfaces)
+            .filter(|(name, interface)| {
+                interface.health() > Health::Idle && !self.is_muted(interface.interface_type, name)
+            })
+            .map(|(name, interface)| (interface.interface_type, name.clone()))
+            .collect();
+        if problems.is_empty() {
+            self.toasts.push("no problems to mute", Severity::Info);
+            return;
+        }
+        let plural = if problems.len() == 1 { "" } else { "s" };
+        self.toasts.push(
+            format!("muted {} problem{}", problems.len(), plural),
+            Severity::Info,
+        );
+        for (interface_type, name) in problems {
+            self.muted.entry(interface_type).or_default().insert(name);
+        }
+    }
+
+    fn clear_filter(&mut self) {
+        if !self.filter.is_empty() {
+            self.filter.clear();
+            self.toasts.push("filter cleared", Severity::Info);
+        }
+    }
+
+    /// Mutes the selected interface, e.g. during planned maintenance: it is
+    /// drawn neutrally and left out of problem navigation and the history.
+    fn toggle_mute(&mut self, apps: &[App]) {
+        if let Some((name, interface)) = self.selected(apps) {
+            let shown = self.name(interface.interface_type, name);
+            let names = self.muted.entry(interface.interface_type).or_default();
+            if names.remove(name) {
+                if names.is_empty() {
+                    self.muted.remove(interface.interface_type);
+                }
+                self.toasts
+                    .push(format!("unmuted {}", shown), Severity::Info);
+            } else {
+                names.insert(name.clone());
+                self.toasts.push(format!("muted {}", shown), Severity::Info);
+            }
+        } else {
+            self.toasts.push("no interface selected", Severity::Warn);
+        }
+    }
+
+    /// Whether the selection follows the latest change right now, which it
+    /// doesn't while the keys or the mouse are in use.
+    fn following(&self, now: Instant) -> bool {
+        self.follow
+            && self
+                .last_input
+                .is_none_or(|t| now.saturating_duration_since(t) >= FOLLOW_RESUME)
+    }
+
+    /// Focuses and selects an interface of a topic, if it is shown.
+    fn select_interface(&mut self, apps: &[App], pane: usize, name: &str) {
+        let found = self
+            .columns(apps)
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.pane == pane)
+            .find_map(|(index, column)| {
+                self.entries(column)
+                    .iter()
+                    .position(|entry| matches!(entry, Entry::Interface(other, _) if *other == name))
+                    .map(|row| (index, row))
+            });
+        if let Some((column, row)) = found {
+            if column != self.focus {
+                self.focus = column;
+                self.list_state = ListState::default();
+            }
+            self.list_state.select(Some(row));
+        }
+    }
+
+    /// Collapses the subgroup or the active interfaces whose header is
+    /// selected, or expands them again.
+    fn toggle_group(&mut self, apps: &[App]) {
+        let Some(column) = self.focused(apps) else {
+            return;
+        };
+        let index = self.list_state.selected();
+        let Some(Entry::Header(group)) = index.and_then(|i| self.entries(&column).get(i).copied())
+        else {
+            return;
+        };
+        if group.active {
+            if !self.expanded_active.remove(column.key) {
+                self.expanded_active.insert(column.key.to_string());
+            }
+        } else {
+            let key = (column.key.to_string(), group.key.to_string());
+            if !self.collapsed.remove(&key) {
+                self.collapsed.insert(key);
+            }
+        }
+    }
+
+    /// Asks for subscribing to the topics again, told when it is done.
+    fn resubscribe(&mut self) {
+        let asked = self
+            .resubscribe
+            .as_ref()
+            .is_some_and(|resubscribe| resubscribe.send(()).is_ok());
+        if asked {
+            self.toasts.push("re-subscribing…", Severity::Info);
+        } else {
+            self.toasts
+                .push("nothing to re-subscribe to", Severity::Warn);
+        }
+    }
+
+    /// Copies the shown interfaces to the clipboard, for pasting the status
+    /// into a chat.
+    fn copy_snapshot(&mut self, apps: &[App], now: Instant) {
+        match crate::clipboard::copy(snapshot(apps, self, now)) {
+            Ok(()) => self.toasts.push("copied a snapshot", Severity::Info),
+            Err(e) => self
+                .toasts
+                .push(format!("could not copy: {}", e), Severity::Error),
+        }
+    }
+
+    /// Does what an action does, breaking once the monitor should quit.
+    fn perform(&mut self, apps: &[App], action: Action, now: Instant) -> ControlFlow<()> {
+        match action {
+            Action::Quit => return ControlFlow::Break(()),
+            Action::Filter => self.editing_filter = true,
+            Action::Note => self.start_note(apps),
+            Action::Resubscribe => self.resubscribe(),
+            Action::Help => self.show_help = !self.show_help,
+            Action::Palette => self.palette = Some(Palette::default()),
+            Action::NextProblem => self.jump_to_problem(apps, 1),
+            Action::PreviousProblem => self.jump_to_problem(apps, -1),
+            Action::History => self.show_history = !self.show_history,
+            Action::CollapseRuns if self.show_history => self.collapse_runs = !self.collapse_runs,
+            Action::Down if self.grid => self.move_selection(apps, self.grid_width.max(1) as isize),
+            Action::Up if self.grid => {
+                self.move_selection(apps, -(self.grid_width.max(1) as isize))
+            }
+            Action::Right if self.grid => self.step_grid(apps, 1),
+            Action::Left if self.grid => self.step_grid(apps, -1),
+            Action::Down => self.move_selection(apps, 1),
+            Action::Up => self.move_selection(apps, -1),
+            Action::Right => self.move_focus(apps, 1),
+            Action::Left => self.move_focus(apps, -1),
+            Action::MoveRight => self.move_column(apps, 1),
+            Action::MoveLeft => self.move_column(apps, -1),
+            Action::Pin => self.toggle_pin(apps),
+            Action::Mute => self.toggle_mute(apps),
+            Action::MuteProblems => self.mute_problems(apps),
+            Action::Redact => self.redact = !self.redact,
+            Action::Snapshot => self.copy_snapshot(apps, now),
+            Action::ToggleGroup => self.toggle_group(apps),
+            Action::Follow => {
+                self.follow = !self.follow;
+                // Taking effect right away, not once the keys are left alone.
+                self.last_input = None;
+                let text = if self.follow {
+                    "following the latest change"
+                } else {
+                    "stopped following"
+                };
+                self.toasts.push(text, Severity::Info);
+            }
+            Action::Payload => self.show_payload = !self.show_payload,
+            Action::Density => self.density = self.density.toggled(),
+            Action::Grid => self.grid = !self.grid,
+            Action::CollapseActive => {
+                self.collapse_active = !self.collapse_active;
+                self.expanded_active.clear();
+            }
+            Action::Unified => {
+                self.unified = !self.unified;
+                self.focus = 0;
+                self.list_state = ListState::default();
+            }
+            Action::Sort if self.unified => self.sort = self.sort.next(),
+            Action::Report => self.show_report = !self.show_report,
+            Action::Resume if self.paused.is_some() => {
+                self.paused = None;
+                self.toasts.push("resumed", Severity::Info);
+            }
+            Action::UnmuteAll if !self.muted.is_empty() => {
+                self.muted.clear();
+                self.toasts.push("unmuted all interfaces", Severity::Info);
+            }
+            Action::Back => {
+                if self.show_help {
+                    self.show_help = false;
+                } else if self.show_report {
+                    self.show_report = false;
+                } else {
+                    self.clear_filter();
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// The selected interface of the focused column, `None` on a header.
+    fn selected<'a>(&self, apps: &'a [App]) -> Option<(&'a String, &'a Interface)> {
+        let index = self.list_state.selected()?;
+        match self.entries(&self.focused(apps)?).get(index)? {
+            Entry::Interface(name, interface) => Some((name, interface)),
+            Entry::Header(_) => None,
+        }
+    }
+
+    /// Keeps the focus on an existing column and the selection within its
+    /// lines.
+    fn clamp_selection(&mut self, apps: &[App]) {
+        let columns = self.columns(apps).len();
+        if self.focus >= columns {
+            self.focus = columns.saturating_sub(1);
+            self.list_state = ListState::default();
+        }
+        let len = self.focused(apps).map_or(0, |c| self.entries(&c).len());
+        let selected = match self.list_state.selected() {
+            _ if len == 0 => None,
+            Some(index) => Some(index.min(len - 1)),
+            None => Some(0),
+        };
+        self.list_state.select(selected);
+    }
+
+    fn move_selection(&mut self, apps: &[App], delta: isize) {
+        let index = self.list_state.selected().unwrap_or(0);
+        self.list_state
+            .select(Some(index.saturating_add_signed(delta)));
+        self.clamp_selection(apps);
+    }
+
+    /// Moves the selection one cell across the grid, to the neighbouring
+    /// column past either end of the focused one.
+    fn step_grid(&mut self, apps: &[App], delta: isize) {
+        let len = self.focused(apps).map_or(0, |c| self.visible(&c).len());
+        let index = self.list_state.selected().unwrap_or(0);
+        match index.checked_add_signed(delta) {
+            Some(next) if next < len => self.list_state.select(Some(next)),
+            _ => {
+                self.move_focus(apps, delta);
+                if delta < 0 {
+                    self.list_state.select(Some(usize::MAX));
+                    self.clamp_selection(apps);
+                }
+            }
+        }
+    }
+
+    fn move_focus(&mut self, apps: &[App], delta: isize) {
+        let columns = self.columns(apps).len().max(1) as isize;
+        self.focus = (self.focus as isize + delta).rem_euclid(columns) as usize;
+        self.list_state = ListState::default();
+        self.clamp_selection(apps);
+    }
+
+    /// Swaps the focused column with its neighbour in the same pane, keeping
+    /// the focus on it, and stores the new order.
+    fn move_column(&mut self, apps: &[App], delta: isize) {
+        let columns = self.columns(apps);
+        let Some(target) = self.focus.checked_add_signed(delta) else {
+            return;
+        };
+        let (Some(focused), Some(neighbour)) = (columns.get(self.focus), columns.get(target))
+        else {
+            return;
+        };
+        if focused.pane != neighbour.pane {
+            return;
+        }
+        let mut order: Vec<String> = columns
+            .iter()
+            .filter(|column| column.pane == focused.pane)
+            .map(|column| column.key.to_string())
+            .collect();
+        let first = self.focus - columns.iter().position(|c| c.pane == focused.pane).unwrap();
+        order.swap(first, first.saturating_add_signed(delta));
+        // Keep where the columns that aren't shown now were put.
+        for key in &self.prefs.column_order {
+            if !order.contains(key) {
+                order.push(key.clone());
+            }
+        }
+        self.prefs.column_order = order;
+        self.focus = target;
+        if let Err(e) = self.prefs.save() {
+            r2r::log_warn!("monitor", "Could not save UI preferences: '{}'.", e);
+            self.toasts.push(
+                format!("could not save the column order: {}", e),
+                Severity::Error,
+            );
+        }
+    }
+
+    /// Selects the interface under a click on a column's overview bar, which
+    /// scrolls the list to it.
+    fn click(&mut self, apps: &[App], x: u16, y: u16) {
+        let hit = self
+            .minimaps
+            .iter()
+            .find(|m| m.area.y == y && m.area.x <= x && x < m.area.right());
+        if let Some((column, row)) = hit.and_then(|m| Some((m.column, m.row_at(x)?))) {
+            if column != self.focus {
+                self.focus = column;
+                self.list_state = ListState::default();
+            }
+            self.list_state.select(Some(row));
+            self.clamp_selection(apps);
+        }
+    }
+
+    /// Moves focus and selection to the next visible interface that isn't
+    /// healthy, or the header of a collapsed subgroup holding one, searching
+    /// the columns in order and wrapping around. A negative `delta` searches
+    /// backwards.
+    fn jump_to_problem(&mut self, apps: &[App], delta: isize) {
+        let problems: Vec<(usize, usize)> = self
+            .columns(apps)
+            .iter()
+            .enumerate()
+            .flat_map(|(index, column)| {
+                self.entries(column)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, entry)| match entry {
+                        Entry::Interface(name, interface) => {
+                            interface.health() > Health::Idle
+                                && !self.is_muted(interface.interface_type, name)
+                        }
+                        Entry::Header(group) => {
+                            group.collapsed && group.worst.is_some_and(|w| w > Health::Idle)
+                        }
+                    })
+                    .map(move |(row, _)| (index, row))
+            })
+            .collect();
+        let current = (self.focus, self.list_state.selected().unwrap_or(0));
+        let target = if delta > 0 {
+            problems.iter().find(|p| **p > current).or(problems.first())
+        } else {
+            problems
+                .iter()
+                .rev()
+                .find(|p| **p < current)
+                .or(problems.last())
+        };
+        if let Some(&(column, row)) = target {
+            if column != self.focus {
+                self.focus = column;
+                self.list_state = ListState::default();
+            }
+            self.list_state.select(Some(row));
+        }
+    }
+}
+
+/// A payload pretty-printed as JSON, its keys, strings, numbers and other
+/// literals each in their own color.
+fn json_lines(value: &serde_json::Value) -> Vec<Line<'static>> {
+    let mut lines = vec![vec![Span::raw("  ")]];
+    push_json(&mut lines, value, 2);
+    lines.into_iter().map(Line::from).collect()
+}
+
+/// Appends `value` to the last of `lines`, starting new lines indented by
+/// `indent` for the members of objects and arrays.
+fn push_json(lines: &mut Vec<Vec<Span<'static>>>, value: &serde_json::Value, indent: usize) {
+    use serde_json::Value;
+    let literal = |text: String, color: Color| Span::styled(text, Style::default().fg(color));
+    let members: Vec<(Option<&String>, &Value)> = match value {
+        Value::Object(map) => map.iter().map(|(k, v)| (Some(k), v)).collect(),
+        Value::Array(items) => items.iter().map(|v| (None, v)).collect(),
+        scalar => {
+            let color = match scalar {
+                Value::String(_) => Color::Green,
+                Value::Number(_) => Color::Cyan,
+                _ => Color::Magenta,
+            };
+            lines
+                .last_mut()
+                .unwrap()
+                .push(literal(scalar.to_string(), color));
+            return;
+        }
+    };
+    let (open, close) = if value.is_object() {
+        ("{", "}")
+    } else {
+        ("[", "]")
+    };
+    if members.is_empty() {
+        lines
+            .last_mut()
+            .unwrap()
+            .push(Span::raw(format!("{}{}", open, close)));
+        return;
+    }
+    lines.last_mut().unwrap().push(Span::raw(open));
+    let count = members.len();
+    for (i, (key, member)) in members.into_iter().enumerate() {
+        let mut line = vec![Span::raw(" ".repeat(indent + 2))];
+        if let Some(key) = key {
+            line.push(literal(Value::String(key.clone()).to_string(), Color::Blue));
+            line.push(Span::raw(": "));
+        }
+        lines.push(line);
+        push_json(lines, member, indent + 2);
+        if i + 1 < count {
+            lines.last_mut().unwrap().push(Span::raw(","));
+        }
+    }
+    lines.push(vec![Span::raw(" ".repeat(indent)), Span::raw(close)]);
+}
+
+// The narrowest a column of `--expanded-help` gets, longer help is cut.
+const HELP_COLUMN_WIDTH: usize = 48;
+
+/// The help of every key laid out in as many columns as fit in `width`,
+/// filled top to bottom.
+fn help_table(keymap: &Keymap, width: u16) -> Vec<Line<'static>> {
+    let help = keymap.help();
+    let columns = (width as usize / HELP_COLUMN_WIDTH).clamp(1, help.len().max(1));
+    let cell = width as usize / columns;
+    let rows = help.len().div_ceil(columns);
+    (0..rows)
+        .map(|row| {
+            let cells: Vec<String> = help
+                .iter()
+                .skip(row)
+                .step_by(rows)
+                .map(|line| {
+                    let line = if line.chars().count() + 2 > cell {
+                        let cut: String = line.chars().take(cell.saturating_sub(3)).collect();
+                        format!("{}…", cut)
+                    } else {
+                        line.clone()
+                    };
+                    format!("{:<cell$}", line)
+                })
+                .collect();
+            Line::from(Span::styled(
+                cells.concat(),
+                Style::default().fg(Color::DarkGray),
+            ))
+        })
+        .collect()
+}
+
+/// How the monitor uses the terminal, set from the command line.
+#[derive(Debug, Default)]
+pub struct UiOptions {
+    // Render inline below the cursor and leave the last frame in the scrollback.
+    pub no_alt_screen: bool,
+    pub max_fps: u32,
+    pub colors: bool,
+    pub theme: Option<Theme>,
+    pub ascii: bool,
+    // How long an interface stays highlighted after its state changed.
+    pub flash: Option<Duration>,
+    // How long an interface may go without an update before it counts as stale.
+    pub stale: Option<Duration>,
+    // When the totals line calls the system degraded or critical.
+    pub banner: Banner,
+    // One line per interface, or room for the age and message on another.
+    pub density: Density,
+    pub severity: SeverityOrder,
+    // Columns by what this captures from the names instead of by interface type.
+    pub group_by: Option<Regex>,
+    // Header rows within the columns by what this captures from the names.
+    pub subgroup_by: Option<Regex>,
+    // The compared topics, in the order of the apps given to the monitor.
+    pub topics: Vec<String>,
+    pub keymap: Keymap,
+    pub redact: bool,
+    // Freeze the view the moment an interface goes to Error.
+    pub pause_on_error: bool,
+    pub zone: Zone,
+    pub grid: bool,
+    pub collapse_active: bool,
+    pub unified: bool,
+    pub follow: bool,
+    pub expanded_help: bool,
+    // What the `--config` file changed to, when it is reloaded.
+    pub reloads: Option<UnboundedReceiver<Reload>>,
+    pub resubscribe: Option<UnboundedSender<()>>,
+}
+
+/// Parses a `--group-by` expression, a regex optionally written between
+/// slashes as in `/(\w+)/`. Its first capture group, or else its whole match,
+/// names the column of an interface.
+pub fn parse_group_by(expression: &str) -> Result<Regex, regex::Error> {
+    let pattern = expression
+        .strip_prefix('/')
+        .and_then(|rest| rest.strip_suffix('/'))
+        .unwrap_or(expression);
+    Regex::new(pattern)
+}
+
+/// Whether the terminal is likely to show colors, honoring `NO_COLOR`.
+pub fn detect_colors() -> bool {
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    match std::env::var("TERM") {
+        Ok(term) => term != "dumb" && crossterm::style::available_color_count() >= 8,
+        Err(_) => false,
+    }
+}
+
+/// Whether the terminal is likely to show more than ASCII: with a UTF-8
+/// locale, and neither the Linux console nor a serial or dumb terminal.
+pub fn detect_unicode() -> bool {
+    let term = std::env::var("TERM").unwrap_or_default();
+    if matches!(
+        term.as_str(),
+        "dumb" | "linux" | "vt100" | "vt102" | "vt220"
+    ) {
+        return false;
+    }
+    // The first of these that is set decides the character set, without
+    // any it is the ASCII of the C locale.
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        .is_some_and(|locale| {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+// How often the monitor wakes up without input to pick up new states.
+const TICK: Duration = Duration::from_millis(100);
+
+// Wall-clock times in the footer and the transition log, in the `--tz` zone.
+const CLOCK_FORMAT: &str = "%H:%M:%S";
+
+// How often the state distribution is sampled for the history view.
+const SAMPLE_PERIOD: Duration = Duration::from_secs(1);
+
+// Whether the terminal is set up for the monitor, and then whether on the
+// alternate screen, so that it is restored exactly once.
+static SET_UP: Mutex<Option<bool>> = Mutex::new(None);
+
+static PANIC_HOOK: Once = Once::new();
+
+/// Puts the terminal into every mode the monitor uses: raw, with mouse
+/// capture and bracketed paste, and on the alternate screen unless rendering
+/// inline. A panic restores it before the panic is reported.
+fn setup_terminal(no_alt_screen: bool) -> io::Result<Terminal<CrosstermBackend<Stdout>>> {
+    PANIC_HOOK.call_once(|| {
+        let report = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            report(info);
+        }));
+    });
+    // Set before any mode is, so a failure part way is restored too.
+    *SET_UP.lock().unwrap() = Some(!no_alt_screen);
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnableMouseCapture, EnableBracketedPaste)?;
+    let viewport = if no_alt_screen {
+        let (_, rows) = crossterm::terminal::size()?;
+        Viewport::Inline(rows)
+    } else {
+        execute!(stdout, EnterAlternateScreen)?;
+        Viewport::Fullscreen
+    };
+    Terminal::with_options(CrosstermBackend::new(stdout), TerminalOptions { viewport })
+}
+
+/// Undoes every mode `setup_terminal` enabled. Does nothing unless the
+/// terminal is set up, so every exit path can call it, the panic hook too.
+pub fn restore_terminal() {
+    let Some(alt_screen) = SET_UP.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    // On the way out there is nothing better to do about a failure than to
+    // go on restoring the rest.
+    let _ = disable_raw_mode();
+    let mut stdout = io::stdout();
+    let _ = execute!(stdout, DisableBracketedPaste, DisableMouseCapture);
+    if alt_screen {
+        let _ = execute!(stdout, LeaveAlternateScreen);
+    } else {
+        // Move below the final frame so the shell prompt does not overwrite it.
+        println!();
+    }
+    let _ = execute!(stdout, Show);
+}
+
+pub async fn spawn_monitor(
+    shared_apps: &[Arc<RwLock<App>>],
+    options: UiOptions,
+) -> Result<(), MonitorError> {
+    let result = match setup_terminal(options.no_alt_screen) {
+        Ok(terminal) => run(terminal, shared_apps, options).await,
+        Err(e) => Err(e.into()),
+    };
+    restore_terminal();
+    result
+}
+
+/// What the monitor reacts to, from the terminal, the apps and the clock.
+enum Event {
+    Input(KeyEvent),
+    Mouse(MouseEvent),
+    // Pasted text arrives whole instead of as keys that would run actions.
+    Paste(String),
+    Resize,
+    // The apps as read after any of them changed.
+    StateChanged(Vec<App>),
+    Reloaded(Reload),
+    // Time passed, for the ages, the clock, the history and the toasts.
+    Tick,
+}
+
+/// Everything the monitor keeps between events. `update` applies an event
+/// to it and `render` draws it, neither waits for anything.
+struct Model {
+    view: View,
+    pause_on_error: bool,
+    // The apps as last read, shared with a pause and every event handled
+    // instead of copied, and the time of the event being handled.
+    live: Arc<Vec<App>>,
+    now: Instant,
+    // The last transition of each topic checked for `--pause-on-error`.
+    checked: Vec<Option<u64>>,
+    last_sample: Option<Instant>,
+    // Only redraw when the app, the view, the terminal size or a displayed age changed.
+    dirty: bool,
+    drawn_generations: Vec<u64>,
+    // When the first of the age labels drawn shows another value, and the
+    // second of the clock drawn.
+    ages_change: Option<Instant>,
+    drawn_second: u64,
+    // The shortest time between two draws, and when the last one was.
+    frame: Duration,
+    drawn_at: Option<Instant>,
+}
+
+impl Model {
+    fn new(options: UiOptions, topics: usize) -> Model {
+        let view = View {
+            prefs: Prefs::load(),
+            colors: options.colors,
+            theme: options.theme,
+            ascii: options.ascii,
+            flash: options.flash,
+            stale: options.stale,
+            banner: options.banner,
+            density: options.density,
+            severity: options.severity,
+            group_by: options.group_by,
+            subgroup_by: options.subgroup_by,
+            topics: options.topics,
+            keymap: options.keymap,
+            redact: options.redact,
+            zone: options.zone,
+            grid: options.grid,
+            collapse_active: options.collapse_active,
+            unified: options.unified,
+            follow: options.follow,
+            expanded_help: options.expanded_help,
+            resubscribe: options.resubscribe,
+            ..View::default()
+        };
+        Model {
+            view,
+            pause_on_error: options.pause_on_error,
+            live: Arc::default(),
+            now: Instant::now(),
+            checked: vec![None; topics],
+            last_sample: None,
+            dirty: true,
+            drawn_generations: Vec::new(),
+            ages_change: None,
+            drawn_second: 0,
+            frame: Duration::from_secs(1) / options.max_fps.max(1),
+            drawn_at: None,
+        }
+    }
+
+    /// The apps and the time as shown. While paused everything is shown as
+    /// it was, down to the ages.
+    fn shown(&self) -> (Arc<Vec<App>>, Instant) {
+        match &self.view.paused {
+            Some(pause) => (pause.apps.clone(), pause.at),
+            None => (self.live.clone(), self.now),
+        }
+    }
+
+    fn update(&mut self, event: Event) -> ControlFlow<()> {
+        self.now = Instant::now();
+        match event {
+            Event::Input(key) => {
+                self.view.last_input = Some(self.now);
+                self.dirty = true;
+                return self.key(key);
+            }
+            Event::Mouse(mouse) => {
+                if mouse.kind != MouseEventKind::Moved {
+                    self.view.last_input = Some(self.now);
+                }
+                if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+                    let (apps, _) = self.shown();
+                    self.view.click(&apps, mouse.column, mouse.row);
+                    self.dirty = true;
+                }
+            }
+            Event::Paste(text) => {
+                let text = text.replace(['\r', '\n'], "");
+                if let Some(palette) = &mut self.view.palette {
+                    palette.query.push_str(&text);
+                    palette.selected = 0;
+                } else if let Some((_, note)) = &mut self.view.note {
+                    note.push_str(&text);
+                } else if self.view.editing_filter {
+                    self.view.filter.push_str(&text);
+                }
+                self.dirty = true;
+            }
+            Event::Resize => self.dirty = true,
+            Event::StateChanged(apps) => {
+                self.live = Arc::new(apps);
+                self.changed(&self.live.clone());
+            }
+            Event::Reloaded(reload) => {
+                match reload {
+                    Reload::Applied {
+                        flash,
+                        stale,
+                        keymap,
+                        banner,
+                        density,
+                        severity,
+                    } => {
+                        self.view.flash = flash;
+                        self.view.stale = stale;
+                        self.view.keymap = keymap;
+                        self.view.banner = banner;
+                        self.view.density = density;
+                        self.view.severity = severity;
+                        self.view.toasts.push("reloaded the config", Severity::Info);
+                    }
+                    Reload::Rejected(e) => self
+                        .view
+                        .toasts
+                        .push(format!("config not reloaded: {}", e), Severity::Error),
+                    Reload::Resubscribed(failed) if failed.is_empty() => {
+                        self.view.toasts.push("re-subscribed", Severity::Info)
+                    }
+                    Reload::Resubscribed(failed) => self.view.toasts.push(
+                        format!("could not re-subscribe: {}", failed.join(", ")),
+                        Severity::Error,
+                    ),
+                }
+                self.dirty = true;
+            }
+            Event::Tick => {
+                let (apps, now) = self.shown();
+                if self
+                    .last_sample
+                    .is_none_or(|t| now.duration_since(t) >= SAMPLE_PERIOD)
+                {
+                    self.last_sample = Some(now);
+                    // The history follows the first topic only.
+                    self.view.history.sample(&apps[0], &self.view.muted);
+                    self.dirty |= self.view.show_history;
+                }
+                self.dirty |= self.view.toasts.tick(now);
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Pauses on a new error with `--pause-on-error` and selects the latest
+    /// change with `--follow`.
+    fn changed(&mut self, apps: &Arc<Vec<App>>) {
+        let view = &mut self.view;
+        // The topic and interface of the latest change, for `--follow`.
+        let mut latest: Option<(usize, &Transition)> = None;
+        for (pane, (app, checked)) in apps.iter().zip(&mut self.checked).enumerate() {
+            let new: Vec<&Transition> = app.transitions_after(*checked).collect();
+            let Some(last) = new.last() else {
+                continue;
+            };
+            *checked = Some(last.seq);
+            let unmuted = new
+                .iter()
+                .rev()
+                .find(|t| !view.is_muted(t.interface_type, &t.name));
+            if let Some(&transition) = unmuted {
+                if latest.is_none_or(|(_, other)| transition.at >= other.at) {
+                    latest = Some((pane, transition));
+                }
+            }
+            let error = new
+                .iter()
+                .find(|t| t.state == State::Error && !view.is_muted(t.interface_type, &t.name));
+            if let (true, None, Some(error)) = (self.pause_on_error, &view.paused, error) {
+                view.paused = Some(Pause {
+                    apps: apps.clone(),
+                    at: self.now,
+                    reason: format!(
+                        "{} {} went to {}",
+                        error.interface_type,
+                        view.name(error.interface_type, &error.name),
+                        error.to
+                    ),
+                });
+                self.dirty = true;
+            }
+        }
+        if let (Some((pane, transition)), true, None) =
+            (latest, view.following(self.now), &view.paused)
+        {
+            let name = transition.name.clone();
+            view.select_interface(apps, pane, &name);
+            self.dirty = true;
+        }
+    }
+
+    /// A key goes to the palette or the filter while either is open, and
+    /// otherwise runs the action it is bound to.
+    fn key(&mut self, key: KeyEvent) -> ControlFlow<()> {
+        // In raw mode Ctrl-C is no signal, it quits from anywhere as it would.
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return ControlFlow::Break(());
+        }
+        let (apps, now) = self.shown();
+        let view = &mut self.view;
+        if let Some(palette) = &mut view.palette {
+            match key.code {
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    palette.query.push(c);
+                    palette.selected = 0;
+                }
+                KeyCode::Backspace => {
+                    palette.query.pop();
+                    palette.selected = 0;
+                }
+                KeyCode::Up => palette.selected = palette.selected.saturating_sub(1),
+                KeyCode::Down => {
+                    let matching = palette.matches(&view.keymap).len();
+                    palette.selected = (palette.selected + 1).min(matching.saturating_sub(1));
+                }
+                KeyCode::Enter => {
+                    let chosen = palette.matches(&view.keymap).get(palette.selected).cloned();
+                    view.palette = None;
+                    if let Some(command) = chosen {
+                        return view.perform(&apps, command.action, now);
+                    }
+                }
+                KeyCode::Esc => view.palette = None,
+                _ => {}
+            }
+        } else if let Some((_, note)) = &mut view.note {
+            match key.code {
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => note.push(c),
+                KeyCode::Backspace => {
+                    note.pop();
+                }
+                KeyCode::Enter => view.save_note(),
+                KeyCode::Esc => view.note = None,
+                _ => {}
+            }
+        } else if view.editing_filter {
+            match key.code {
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    view.filter.push(c)
+                }
+                KeyCode::Backspace => {
+                    view.filter.pop();
+                }
+                KeyCode::Tab => view.substring_filter = !view.substring_filter,
+                KeyCode::Enter => view.editing_filter = false,
+                KeyCode::Esc => {
+                    view.clear_filter();
+                    view.editing_filter = false;
+                }
+                _ => {}
+            }
+        } else {
+            let palette =
+                key.code == KeyCode::Char('p') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if palette {
+                view.palette = Some(Palette::default());
+            } else if let Some(action) = view.keymap.action(key) {
+                return view.perform(&apps, action, now);
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Draws the model unless nothing shown changed since the last frame.
+    fn render<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> io::Result<()> {
+        let (apps, now) = self.shown();
+        // Checked many times a second, without allocating or formatting.
+        let generations = apps.iter().map(|app| app.generation);
+        let second = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        if self.dirty
+            || !generations.eq(self.drawn_generations.iter().copied())
+            || self.ages_change.is_some_and(|change| now >= change)
+            || second != self.drawn_second
+        {
+            self.view.clamp_selection(&apps);
+            terminal.draw(|f| draw_ui(f, &apps, &mut self.view, now))?;
+            self.dirty = false;
+            self.drawn_generations = apps.iter().map(|app| app.generation).collect();
+            self.ages_change = next_age_change(&apps, now);
+            self.drawn_second = second;
+            self.drawn_at = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    /// When the next frame may be drawn, `None` when it may be right away.
+    fn next_frame(&self) -> Option<Instant> {
+        self.drawn_at
+            .map(|drawn_at| drawn_at + self.frame)
+            .filter(|&next| next > Instant::now())
+    }
+}
+
+/// SIGWINCH, for the terminals and multiplexers like tmux whose resizing
+/// crossterm doesn't always report.
+struct WindowChanges {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl WindowChanges {
+    fn new() -> WindowChanges {
+        WindowChanges {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::window_change())
+                .ok(),
+        }
+    }
+
+    /// Waits for the next change, forever where there are no signals.
+    async fn next(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = &mut self.signal {
+            if signal.recv().await.is_some() {
+                return;
+            }
+            self.signal = None;
+        }
+        std::future::pending().await
+    }
+}
+
+/// Runs the monitor until it is quit, on one terminal for the whole run:
+/// each draw is diffed against the previous buffer and only changed cells
+/// are written, so it is never cleared between frames. A forced redraw, on
+/// a resize event or a key, writes next to nothing when nothing changed,
+/// ratatui only clears once the size really is another.
+async fn run(
+    mut terminal: Terminal<CrosstermBackend<Stdout>>,
+    shared_apps: &[Arc<RwLock<App>>],
+    mut options: UiOptions,
+) -> Result<(), MonitorError> {
+    let mut reloads = options.reloads.take();
+    let mut model = Model::new(options, shared_apps.len());
+    let mut events = EventStream::new();
+    let mut window_changes = WindowChanges::new();
+    // Waking up every tick to pick up new states and ages, also while keys
+    // keep arriving.
+    let mut ticks = tokio::time::interval(TICK);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        // The apps are copied only when they changed, the generations tell.
+        let changed = shared_apps.len() != model.live.len()
+            || shared_apps
+                .iter()
+                .zip(model.live.iter())
+                .any(|(shared, app)| shared.read().unwrap().generation != app.generation);
+        if changed {
+            let apps = shared_apps
+                .iter()
+                .map(|app| app.read().unwrap().clone())
+                .collect();
+            let _ = model.update(Event::StateChanged(apps));
+        }
+        // Within a frame of the last draw the next one waits for the frame to
+        // end, so a burst of events is drawn once, at a steady rate, while a
+        // key after a quiet frame is drawn at once.
+        let next_frame = model.next_frame();
+        if next_frame.is_none() {
+            model.render(&mut terminal)?;
+        }
+
+        // Wait without blocking the runtime the node is spun on.
+        let event = tokio::select! {
+            Some(event) = events.next() => match event? {
+                CEvent::Key(key) => Event::Input(key),
+                CEvent::Mouse(mouse) => Event::Mouse(mouse),
+                CEvent::Paste(text) => Event::Paste(text),
+                CEvent::Resize(..) => Event::Resize,
+                _ => continue,
+            },
+            Some(reload) = async {
+                match &mut reloads {
+                    Some(reloads) => reloads.recv().await,
+                    None => std::future::pending().await,
+                }
+            } => Event::Reloaded(reload),
+            // Drawing queries the new size, as for a resize event.
+            _ = window_changes.next() => Event::Resize,
+            _ = ticks.tick() => Event::Tick,
+            _ = tokio::time::sleep_until(next_frame.unwrap_or_else(Instant::now).into()),
+                if next_frame.is_some() => continue,
+        };
+        if model.update(event).is_break() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps what is written to the terminal instead of showing it.
+#[derive(Debug, Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Recorder {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The monitor drawn onto a terminal of a fixed size that keeps what is
+/// written to it, for measuring what a frame costs without a terminal.
+#[doc(hidden)]
+pub struct Offscreen {
+    model: Model,
+    terminal: Terminal<CrosstermBackend<Recorder>>,
+    written: Recorder,
+}
+
+impl Offscreen {
+    pub fn new(apps: Vec<App>, width: u16, height: u16) -> io::Result<Offscreen> {
+        let options = UiOptions {
+            max_fps: 30,
+            colors: true,
+            ..UiOptions::default()
+        };
+        let mut model = Model::new(options, apps.len());
+        let _ = model.update(Event::StateChanged(apps));
+        let written = Recorder::default();
+        let terminal = Terminal::with_options(
+            CrosstermBackend::new(written.clone()),
+            TerminalOptions {
+                viewport: Viewport::Fixed(Rect::new(0, 0, width, height)),
+            },
+        )?;
+        Ok(Offscreen {
+            model,
+            terminal,
+            written,
+        })
+    }
+
+    /// Draws what changed since the last frame, as the monitor does on
+    /// every turn of its loop, and takes the bytes that wrote.
+    pub fn draw(&mut self) -> io::Result<Vec<u8>> {
+        self.model.render(&mut self.terminal)?;
+        Ok(std::mem::take(&mut *self.written.0.lock().unwrap()))
+    }
+
+    pub fn set_apps(&mut self, apps: Vec<App>) {
+        let _ = self.model.update(Event::StateChanged(apps));
+    }
+
+    /// Forces the next draw, as a resize event does.
+    pub fn resize(&mut self) {
+        let _ = self.model.update(Event::Resize);
+    }
+
+    pub fn tick(&mut self) {
+        let _ = self.model.update(Event::Tick);
+    }
+}
+
+/// How finely a duration below an hour is shown.
+#[derive(Debug, Clone, Copy)]
+enum Precision {
+    // Whole minutes from a minute on, so that ages stay the same between redraws.
+    Coarse,
+    // Down to the second, so that recent changes stand out.
+    Fine,
+}
+
+/// Formats every duration the monitor shows, largest unit first: "3s", "2m"
+/// or "2m13s", "1h12m", "2d03h".
+fn format_duration(duration: Duration, precision: Precision) -> HumanDuration {
+    HumanDuration(duration, precision)
+}
+
+struct HumanDuration(Duration, Precision);
+
+impl std::fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let secs = self.0.as_secs();
+        match (secs, self.1) {
+            (0..=59, _) => write!(f, "{}s", secs),
+            (60..=3599, Precision::Coarse) => write!(f, "{}m", secs / 60),
+            (60..=3599, Precision::Fine) => write!(f, "{}m{:02}s", secs / 60, secs % 60),
+            (3600..=86399, _) => write!(f, "{}h{:02}m", secs / 3600, secs % 3600 / 60),
+            _ => write!(f, "{}d{:02}h", secs / 86400, secs % 86400 / 3600),
+        }
+    }
+}
+
+/// When the first of the age and state duration labels of the interfaces
+/// shows another value than at `now`, `None` without interfaces.
+fn next_age_change(apps: &[App], now: Instant) -> Option<Instant> {
+    apps.iter()
+        .flat_map(|app| app.columns())
+        .flat_map(|(_, states)| states.values())
+        .flat_map(|interface| {
+            [
+                label_change(interface.updated, now, Precision::Coarse),
+                label_change(interface.changed, now, Precision::Fine),
+            ]
+        })
+        .min()
+}
+
+/// When the duration since `since` is next shown otherwise, following the
+/// units of `HumanDuration`.
+fn label_change(since: Instant, now: Instant, precision: Precision) -> Instant {
+    let secs = now.saturating_duration_since(since).as_secs();
+    let next = match (secs, precision) {
+        (0..=59, _) | (60..=3599, Precision::Fine) => secs + 1,
+        (60..=86399, _) => (secs / 60 + 1) * 60,
+        _ => (secs / 3600 + 1) * 3600,
+    };
+    since + Duration::from_secs(next)
+}
+
+fn health_style(health: Health) -> Style {
+    Style::default().fg(health_color(health))
+}
+
+/// The color a health is shown in, also by the log on a terminal.
+pub fn health_color(health: Health) -> Color {
+    match health {
+        Health::Ok => Color::Green,
+        Health::Idle => Color::Blue,
+        Health::Unknown => Color::Gray,
+        Health::Warn => Color::Yellow,
+        Health::Error => Color::Red,
+    }
+}
+
+/// Tells states apart when colors can't, by the same health the colors show.
+fn state_tag(interface: &Interface) -> &'static str {
+    match interface.health() {
+        Health::Ok => "[A] ",
+        Health::Idle => "[i] ",
+        Health::Unknown => "[?] ",
+        Health::Warn => "[W] ",
+        Health::Error if interface.state == State::Inactive => "[I] ",
+        Health::Error => "[E] ",
+    }
+}
+
+/// Draws a column's overview bar, each cell colored by the worst health of the
+/// interfaces it stands for.
+struct MinimapBar<'a, 'b> {
+    entries: &'a [Entry<'b>],
+    view: &'a View,
+}
+
+impl Widget for MinimapBar<'_, '_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let len = self.entries.len();
+        let cells = len.min(area.width as usize);
+        for cell in 0..cells {
+            let run = &self.entries[cell * len / cells..(cell + 1) * len / cells];
+            let unmuted = run.iter().filter_map(|entry| match entry {
+                Entry::Interface(name, interface)
+                    if !self.view.is_muted(interface.interface_type, name) =>
+                {
+                    Some((name, interface))
+                }
+                _ => None,
+            });
+            let worst =
+                unmuted.max_by_key(|(_, interface)| interface.severity(&self.view.severity));
+            let (symbol, style) = match worst {
+                Some((_, interface)) if self.view.colors => ("█", health_style(interface.health())),
+                Some((_, interface)) => (&state_tag(interface)[1..2], Style::default()),
+                None => ("·", Style::default().fg(Color::DarkGray)),
+            };
+            buf.get_mut(area.x + cell as u16, area.y)
+                .set_symbol(symbol)
+                .set_style(style);
+        }
+    }
+}
+
+/// Resets every color in an area, for terminals that can't show them.
+struct StripColors;
+
+/// Draws the named colors in an area in those of a theme.
+struct Recolor<'a>(&'a Theme);
+
+impl Widget for Recolor<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                let (fg, bg) = (self.0.foreground(cell.fg), self.0.background(cell.bg));
+                cell.set_fg(fg).set_bg(bg);
+            }
+        }
+    }
+}
+
+/// How the selected row of a list stands out: on the selection color of
+/// the theme, or else reversed.
+fn highlight_style(view: &View) -> Style {
+    match view.theme.as_ref().and_then(Theme::selection) {
+        Some(selection) if view.colors => Style::default().bg(selection),
+        _ => Style::default().add_modifier(Modifier::REVERSED),
+    }
+}
+
+/// Replaces whatever is not ASCII in an area by the closest ASCII character,
+/// from the borders and bars ratatui draws to the marks and the text, for
+/// terminals and fonts that show nothing else.
+struct AsciiOnly;
+
+impl Widget for AsciiOnly {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                let Some(c) = cell.symbol.chars().find(|c| !c.is_ascii()) else {
+                    continue;
+                };
+                let mut ascii = [0; 1];
+                cell.set_symbol(to_ascii(c).encode_utf8(&mut ascii));
+            }
+        }
+    }
+}
+
+/// The ASCII character that looks most like `c`.
+fn to_ascii(c: char) -> char {
+    match c {
+        '─' | '━' | '═' | '╌' | '╍' | '┄' | '┅' | '—' | '–' => '-',
+        '│' | '┃' | '║' | '╎' | '╏' | '┆' | '┇' => '|',
+        // The rest of the box drawing characters are corners and junctions.
+        '\u{2500}'..='\u{257f}' => '+',
+        '▁' | '▂' | '▃' => '_',
+        '▄' | '▅' | '▆' | '▇' | '▒' => '=',
+        '\u{2580}'..='\u{259f}' => '#',
+        // Blank braille is what empty chart cells are drawn with.
+        '\u{2800}' => ' ',
+        '\u{2801}'..='\u{28ff}' | '…' | '·' => '.',
+        '←' | '◂' | '◀' => '<',
+        '→' | '▸' | '▶' => '>',
+        '↑' | '▴' | '▲' => '^',
+        '↓' | '▾' | '▼' => 'v',
+        '≠' => '!',
+        '×' | '✗' => 'x',
+        '✓' => '+',
+        '🔇' => 'm',
+        _ => '?',
+    }
+}
+
+impl Widget for StripColors {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                buf.get_mut(x, y).set_fg(Color::Reset).set_bg(Color::Reset);
+            }
+        }
+    }
+}
+
+// The most muted interfaces the footer names, it counts more.
+const MUTED_NAMED: usize = 3;
+
+// The signal bars of the link quality, lit ones and those out.
+const LINK_BARS: [char; link::BARS] = ['▂', '▄', '▆', '█'];
+const LINK_BAR_OUT: char = '·';
+
+// How long the footer keeps telling that interfaces are being evicted.
+const EVICTION_NOTICE: Duration = Duration::from_secs(10);
+
+// How long a newly appeared interface stands out in its column.
+const NEW_HIGHLIGHT: Duration = Duration::from_secs(5);
+
+// How much of an interface's message is shown in its row, the detail pane has all of it.
+const MESSAGE_WIDTH: usize = 24;
+
+// Room for the marks and the tag in front of a name in its row, and the age after it.
+const LABEL_EXTRA: usize = 32;
+
+/// Shortens `text` to at most `width` characters, marking that it was cut.
+fn truncate(text: &str, width: usize) -> Cow<'_, str> {
+    if text.chars().count() <= width {
+        Cow::Borrowed(text)
+    } else {
+        let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+        cut.push('…');
+        Cow::Owned(cut)
+    }
+}
+
+/// How long ago an interface was last updated, for its row.
+fn age_text(interface: &Interface, now: Instant) -> AgeText {
+    AgeText(
+        interface
+            .reported
+            .then(|| now.saturating_duration_since(interface.updated)),
+    )
+}
+
+struct AgeText(Option<Duration>);
+
+impl std::fmt::Display for AgeText {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.0 {
+            Some(age) => write!(f, "{} ago", format_duration(age, Precision::Coarse)),
+            None => f.write_str("not seen yet"),
+        }
+    }
+}
+
+/// The shown interfaces as a markdown table, the topic first when comparing.
+fn snapshot(apps: &[App], view: &View, now: Instant) -> String {
+    let mut header = vec!["type", "name", "state", "updated"];
+    if !view.topics.is_empty() {
+        header.insert(0, "topic");
+    }
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for (pane, app) in apps.iter().enumerate() {
+        for column in view.columns_of(app, pane) {
+            for (name, interface) in view.visible(&column) {
+                let mut row = vec![
+                    interface.interface_type.to_string(),
+                    view.name(interface.interface_type, name).into_owned(),
+                    interface.value.clone(),
+                    age_text(interface, now).to_string(),
+                ];
+                if let Some(topic) = view.topics.get(pane) {
+                    row.insert(0, topic.clone());
+                }
+                rows.push(row);
+            }
+        }
+    }
+    // A `|` in a value would end its cell.
+    let rows: Vec<Vec<String>> = rows
+        .into_iter()
+        .map(|row| row.into_iter().map(|v| v.replace('|', "\\|")).collect())
+        .collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .map(|row| Span::raw(row[i].as_str()).width())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let line = |cells: Vec<String>| {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| {
+                let pad = width.saturating_sub(Span::raw(cell.as_str()).width());
+                format!("{}{}", cell, " ".repeat(pad))
+            })
+            .collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+    let mut text = line(header.iter().map(|h| h.to_string()).collect());
+    text.push_str(&line(widths.iter().map(|&w| "-".repeat(w)).collect()));
+    for row in rows {
+        text.push_str(&line(row));
+    }
+    text
+}
+
+/// How an interface's row looks: colored by its health, bold while it is new
+/// or pinned, reversed while it flashes after a change and gray while muted.
+fn row_style(name: &String, interface: &Interface, view: &View, now: Instant) -> Style {
+    let mut style = health_style(interface.health());
+    if interface.reported && now.saturating_duration_since(interface.first_seen) < NEW_HIGHLIGHT {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if view
+        .flash
+        .is_some_and(|flash| now.saturating_duration_since(interface.changed) < flash)
+    {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+    if view.is_muted(interface.interface_type, name) {
+        style = style.fg(Color::DarkGray);
+    }
+    if view.prefs.pinned.contains(name) {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    style
+}
+
+/// The name of an interface as its row shows it, marked with its state tag
+/// when there are no colors and with whether it is muted, pinned, differs
+/// or reports off its expected rate.
+fn marked_name(
+    name: &String,
+    interface: &Interface,
+    differing: &BTreeSet<&String>,
+    view: &View,
+    now: Instant,
+) -> String {
+    // Built front to back into one string, every row of every column has one.
+    let mut label = String::with_capacity(name.len() + LABEL_EXTRA);
+    if differing.contains(name) {
+        label.push_str("≠ ");
+    }
+    if interface.pace(now).is_some_and(|pace| pace != Pace::OnRate) {
+        label.push_str("~ ");
+    }
+    if view.prefs.pinned.contains(name) {
+        label.push_str("* ");
+    }
+    if view.is_muted(interface.interface_type, name) {
+        label.push_str("🔇 ");
+    }
+    if !view.colors {
+        label.push_str(state_tag(interface));
+    }
+    label.push_str(&view.name(interface.interface_type, name));
+    label
+}
+
+/// The rate an interface reports at next to the one it should, colored by
+/// whether it is within the tolerance.
+fn rate_line(interface: &Interface, rate: link::Rate, now: Instant) -> Line<'static> {
+    let expected = format!(
+        "expected {} Hz ±{}%",
+        rate.hz,
+        (rate.tolerance * 100.0).round()
+    );
+    let observed = interface
+        .arrivals
+        .and_then(|arrivals| arrivals.rate(now))
+        .map_or("measuring".to_string(), |hz| format!("{:.1} Hz", hz));
+    let (verdict, health) = match interface.pace(now) {
+        None => ("", Health::Unknown),
+        Some(Pace::OnRate) => ("", Health::Ok),
+        Some(Pace::Slow) => (", too slow", Health::Warn),
+        Some(Pace::Fast) => (", too fast", Health::Warn),
+        Some(Pace::Silent) => (", silent", Health::Error),
+    };
+    Line::from(vec![
+        Span::raw("rate: "),
+        Span::styled(format!("{}{}", observed, verdict), health_style(health)),
+        Span::raw(format!(", {}", expected)),
+    ])
+}
+
+/// The header row of a subgroup: whether it is collapsed, its name and how
+/// many interfaces it holds, colored by the worst of them.
+fn subgroup_item(group: &Subgroup, view: &View) -> ListItem<'static> {
+    let marker = if group.collapsed { "▸" } else { "▾" };
+    if group.active {
+        let toggle = if group.collapsed {
+            "expand"
+        } else {
+            "collapse"
+        };
+        let label = format!("{} {} active ({})", marker, group.count, toggle);
+        return ListItem::new(label).style(health_style(Health::Ok));
+    }
+    let label = format!(
+        "{} {} ({})",
+        marker,
+        view.name("group", group.key),
+        group.count
+    );
+    let style = group.worst.map_or(Style::default(), health_style);
+    ListItem::new(label).style(style.add_modifier(Modifier::BOLD))
+}
+
+fn state_items<'a>(
+    entries: &[Entry<'a>],
+    differing: &BTreeSet<&String>,
+    view: &View,
+    now: Instant,
+    width: u16,
+) -> Vec<ListItem<'a>> {
+    entries
+        .iter()
+        .map(|entry| {
+            let (name, interface) = match *entry {
+                Entry::Interface(name, interface) => (name, interface),
+                Entry::Header(group) => return subgroup_item(&group, view),
+            };
+            let comfortable = view.density == Density::Comfortable;
+            let mut label = marked_name(name, interface, differing, view, now);
+            if interface.number().is_some() {
+                label.push(' ');
+                label.push_str(&interface.value);
+            }
+            if !comfortable {
+                let _ = write!(label, " ({})", age_text(interface, now));
+            }
+            // The message goes last, so a narrow column cuts it before the age.
+            let mut spans = Vec::with_capacity(4);
+            spans.push(Span::raw(label));
+            // How long the state has held, only where it fits in full. A
+            // numeric value changes with every reading, there it means little.
+            if interface.reported && interface.number().is_none() {
+                let held = format!(
+                    " {} {}",
+                    interface.value,
+                    format_duration(
+                        now.saturating_duration_since(interface.changed),
+                        Precision::Fine
+                    )
+                );
+                if spans[0].width() + Span::raw(held.as_str()).width() <= width as usize {
+                    spans.push(Span::raw(held));
+                }
+            }
+            let style = Style::default().fg(Color::DarkGray);
+            let mut lines = vec![Line::from(spans)];
+            if comfortable {
+                // The second line has room for the whole message.
+                let mut second = vec![Span::styled(
+                    format!("  {}", age_text(interface, now)),
+                    style,
+                )];
+                if let Some(message) = &interface.message {
+                    second.push(Span::styled(format!("  {}", message), style));
+                }
+                lines.push(Line::from(second));
+            } else if let Some(message) = &interface.message {
+                let spans = &mut lines[0].spans;
+                spans.push(Span::styled(" ", style));
+                spans.push(Span::styled(truncate(message, MESSAGE_WIDTH), style));
+            }
+            ListItem::new(lines).style(row_style(name, interface, view, now))
+        })
+        .collect()
+}
+
+// The widest a field of the table gets, however long the values in it are.
+const MAX_FIELD_WIDTH: usize = 40;
+
+/// Draws the rows of a column as a table with a field per column, lined up
+/// whatever the length of the names and states.
+fn draw_table<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    view: &mut View,
+    column: &Column,
+    rows: &[(&String, &Interface)],
+    focused: bool,
+    now: Instant,
+) {
+    let fields: Vec<[String; 6]> = rows
+        .iter()
+        .map(|(name, interface)| {
+            let held = if interface.reported {
+                format_duration(
+                    now.saturating_duration_since(interface.changed),
+                    Precision::Fine,
+                )
+                .to_string()
+            } else {
+                String::new()
+            };
+            [
+                interface.interface_type.to_string(),
+                marked_name(name, interface, &column.differing, view, now),
+                interface.value.clone(),
+                age_text(interface, now).to_string(),
+                held,
+                interface.message.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let header = ["type", "name", "state", "updated", "held", "message"];
+    // Each field as wide as its widest value, the message takes what is left.
+    let widths: Vec<Constraint> = (0..header.len())
+        .map(|i| {
+            let widest = fields
+                .iter()
+                .map(|row| Span::raw(row[i].as_str()).width())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0);
+            if i == header.len() - 1 {
+                Constraint::Percentage(100)
+            } else {
+                Constraint::Length(widest.min(MAX_FIELD_WIDTH) as u16)
+            }
+        })
+        .collect();
+    let table_rows: Vec<Row> = fields
+        .into_iter()
+        .zip(rows)
+        .map(|(row, (name, interface))| {
+            Row::new(row)
+                .style(row_style(name, interface, view, now))
+                .bottom_margin(view.density.lines() - 1)
+        })
+        .collect();
+    let table = Table::new(table_rows)
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .widths(&widths)
+        .highlight_style(highlight_style(view));
+    let selected = view.list_state.selected().filter(|_| focused);
+    let offset = if focused { view.list_state.offset() } else { 0 };
+    let mut state = TableState::default()
+        .with_offset(offset)
+        .with_selected(selected);
+    f.render_stateful_widget(table, area, &mut state);
+    if focused {
+        *view.list_state.offset_mut() = state.offset();
+    }
+}
+
+/// The rows a list of one-line items shows in `height` lines when scrolled
+/// to `offset`, moved just far enough to show the selected row. The same
+/// scrolling as a `List`, which shows the top without a selection.
+fn scrolled_window(
+    len: usize,
+    offset: usize,
+    selected: Option<usize>,
+    height: usize,
+) -> Range<usize> {
+    if len == 0 {
+        return 0..0;
+    }
+    let mut start = offset.min(len - 1);
+    let mut end = (start + height).min(len);
+    let selected = selected.unwrap_or(0).min(len - 1);
+    if selected >= end {
+        end = selected + 1;
+        start = end.saturating_sub(height);
+    }
+    if selected < start {
+        start = selected;
+        end = end.min(selected + height);
+    }
+    start..end
+}
+
+/// Draws one interface column, with its pinned interfaces in a section at the
+/// top that stays in place while the rest of the column scrolls.
+fn draw_column<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    view: &mut View,
+    column: &Column,
+    index: usize,
+    now: Instant,
+) {
+    let focused = index == view.focus;
+    let rows = view.visible(column);
+    let entries = view.entries_of(column.key, &rows);
+    let pinned = rows
+        .iter()
+        .take_while(|(name, _)| view.prefs.pinned.contains(*name))
+        .count();
+    let (pinned_rows, other_rows) = entries.split_at(pinned);
+
+    // The border shows the worst interface in the column, for peripheral vision.
+    let worst = column
+        .rows
+        .iter()
+        .filter(|(name, interface)| !view.is_muted(interface.interface_type, name))
+        .max_by_key(|(_, interface)| interface.severity(&view.severity))
+        .map(|(_, interface)| interface.health());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(worst.map_or(Style::default(), health_style))
+        .title(column.title.as_str());
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if rows.is_empty() && !view.filter.is_empty() && !column.rows.is_empty() {
+        let no_matches = Paragraph::new("no matches").style(Style::default().fg(Color::DarkGray));
+        f.render_widget(no_matches, inner);
+        return;
+    }
+    if view.grid {
+        draw_grid(f, inner, view, &rows, focused);
+        return;
+    }
+    // An overview of the whole column when it doesn't fit, however it is scrolled.
+    let mut inner = inner;
+    let lines = view.density.lines();
+    if entries.len() * lines as usize > inner.height as usize && inner.height > 1 {
+        let minimap = Minimap {
+            area: Rect { height: 1, ..inner },
+            column: index,
+            rows: entries.len(),
+        };
+        let bar = MinimapBar {
+            entries: &entries,
+            view,
+        };
+        f.render_widget(bar, minimap.area);
+        view.minimaps.push(minimap);
+        inner.y += 1;
+        inner.height -= 1;
+    }
+    // The pinned interfaces stay on top of the table, marked but not fixed.
+    if view.unified {
+        draw_table(f, inner, view, column, &rows, focused, now);
+        return;
+    }
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(pinned as u16 * lines),
+                Constraint::Min(0),
+            ]
+            .as_ref(),
+        )
+        .split(inner);
+
+    let highlight = highlight_style(view);
+    let selected = view.list_state.selected().filter(|_| focused);
+
+    let pinned_list = List::new(state_items(
+        pinned_rows,
+        &column.differing,
+        view,
+        now,
+        inner.width,
+    ))
+    .highlight_style(highlight);
+    let mut pinned_state =
+        ListState::default().with_selected(selected.filter(|&index| index < pinned));
+    f.render_stateful_widget(pinned_list, sections[0], &mut pinned_state);
+
+    // Only the rows that fit are formatted, however long the column is.
+    let offset = if focused { view.list_state.offset() } else { 0 };
+    let other_selected = selected.and_then(|index| index.checked_sub(pinned));
+    let height = (sections[1].height.saturating_sub((pinned > 0) as u16) / lines) as usize;
+    let shown = scrolled_window(other_rows.len(), offset, other_selected, height);
+    let mut other_list = List::new(state_items(
+        &other_rows[shown.clone()],
+        &column.differing,
+        view,
+        now,
+        inner.width,
+    ))
+    .highlight_style(highlight);
+    if pinned > 0 {
+        other_list = other_list.block(Block::default().borders(Borders::TOP));
+    }
+    let mut other_state = ListState::default()
+        .with_selected(other_selected.and_then(|index| index.checked_sub(shown.start)));
+    f.render_stateful_widget(other_list, sections[1], &mut other_state);
+    if focused {
+        *view.list_state.offset_mut() = shown.start;
+    }
+}
+
+/// Draws the rows of a column as a wrapped grid of cells, each labeled by its
+/// position in the column and colored by its health, scrolled just far enough
+/// to keep the selection in view.
+fn draw_grid<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    view: &mut View,
+    rows: &[(&String, &Interface)],
+    focused: bool,
+) {
+    let digits = rows.len().to_string().len();
+    // Without colors the cells start with the letter of their state tag.
+    let cell_width = digits + usize::from(!view.colors) + 1;
+    let per_row = (area.width as usize / cell_width).max(1);
+    if focused {
+        view.grid_width = per_row;
+    }
+    let selected = view.list_state.selected().filter(|_| focused);
+    let first_row = selected.map_or(0, |index| {
+        (index / per_row + 1).saturating_sub(area.height as usize)
+    });
+    let lines: Vec<Line> = rows
+        .chunks(per_row)
+        .enumerate()
+        .skip(first_row)
+        .take(area.height as usize)
+        .map(|(row, cells)| {
+            let mut spans = Vec::new();
+            for (i, (name, interface)) in cells.iter().enumerate() {
+                let index = row * per_row + i;
+                let color = if view.is_muted(interface.interface_type, name) {
+                    Color::DarkGray
+                } else {
+                    health_style(interface.health()).fg.unwrap_or(Color::Gray)
+                };
+                let mut style = Style::default().fg(Color::Black).bg(color);
+                if view.prefs.pinned.contains(*name) {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+                if selected == Some(index) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                let tag = if view.colors {
+                    String::new()
+                } else {
+                    state_tag(interface)
+                        .chars()
+                        .nth(1)
+                        .map_or(String::new(), String::from)
+                };
+                spans.push(Span::styled(
+                    format!("{}{:>width$}", tag, index + 1, width = digits),
+                    style,
+                ));
+                spans.push(Span::raw(" "));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), area);
+}
+
+/// Draws the state distribution over time, one stacked chart per interface type.
+fn draw_history<B: Backend>(f: &mut Frame<B>, area: Rect, app: &App, view: &View) {
+    let halves = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area);
+    let columns = app.columns();
+    let constraints = vec![Constraint::Ratio(1, columns.len() as u32); columns.len()];
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(halves[0]);
+    for (i, (title, _)) in columns.iter().enumerate() {
+        let samples = view.history.column(i);
+        let title = match samples.last() {
+            Some(last) => format!(
+                "{} history: {} ok, {} warn, {} error, {} unknown",
+                title, last.ok, last.warn, last.error, last.unknown
+            ),
+            None => format!("{} history", title),
+        };
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let inner = block.inner(rows[i]);
+        f.render_widget(block, rows[i]);
+        let chart = DistributionChart {
+            samples: &samples,
+            colors: view.colors,
+        };
+        f.render_widget(chart, inner);
+    }
+
+    // The transition log, newest first.
+    let now = SystemTime::now();
+    let runs = if view.collapse_runs {
+        history::runs(app.transitions.iter())
+    } else {
+        app.transitions.iter().map(|t| vec![t]).collect()
+    };
+    let items: Vec<ListItem> = runs
+        .iter()
+        .rev()
+        .take(halves[1].height as usize)
+        .map(|run| ListItem::new(transition_line(run, view, now)))
+        .collect();
+    let collapse = view.keymap.key(Action::CollapseRuns);
+    let title = if view.collapse_runs {
+        format!(
+            "Transitions, {} time (collapsed, {} to expand)",
+            view.zone.label(),
+            collapse
+        )
+    } else {
+        format!(
+            "Transitions, {} time ({} to collapse)",
+            view.zone.label(),
+            collapse
+        )
+    };
+    let log = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(log, halves[1]);
+}
+
+/// Describes a run of transitions of one interface, a run of one is a plain transition.
+fn transition_line(run: &[&Transition], view: &View, now: SystemTime) -> String {
+    let first = run[0];
+    let last = run[run.len() - 1];
+    let ago = format_duration(
+        now.duration_since(last.at).unwrap_or_default(),
+        Precision::Coarse,
+    );
+    let prefix = format!(
+        "{} ({} ago)  {} {}: ",
+        view.zone.format(last.at, CLOCK_FORMAT),
+        ago,
+        first.interface_type,
+        view.name(first.interface_type, &first.name)
+    );
+    let mut chain: Vec<&str> = first.from.iter().map(String::as_str).collect();
+    chain.push(&first.to);
+    if run.len() == 1 {
+        if first.from.is_none() {
+            return format!("{}{} (new)", prefix, first.to);
+        }
+        return format!("{}{}", prefix, chain.join("→"));
+    }
+    chain.push(&last.to);
+    let span = last.at.duration_since(first.at).unwrap_or_default();
+    format!(
+        "{}{} ×{} over {}",
+        prefix,
+        chain.join("→"),
+        run.len(),
+        format_duration(span, Precision::Coarse)
+    )
+}
+
+/// The whole-system summary, e.g. "Total: 30 interfaces — 22 active, 3 error,
+/// 5 inactive (2 stale)". Muted interfaces are counted apart from their state,
+/// and not as stale.
+fn totals_line(app: &App, view: &View, now: Instant) -> Line<'static> {
+    let (mut total, mut stale, mut muted, mut not_seen) = (0, 0, 0, 0);
+    let mut by_state = BTreeMap::new();
+    for (_, states) in app.columns() {
+        for (name, interface) in states {
+            total += 1;
+            if view.is_muted(interface.interface_type, name) {
+                muted += 1;
+                continue;
+            }
+            if !interface.reported {
+                not_seen += 1;
+            } else {
+                *by_state.entry(interface.state).or_insert(0) += 1;
+            }
+            let since = now.saturating_duration_since(interface.updated);
+            if interface.reported && view.stale.is_some_and(|stale| since >= stale) {
+                stale += 1;
+            }
+        }
+    }
+    let count = |state| by_state.get(&state).copied().unwrap_or(0);
+    let mut parts = vec![
+        format!("{} active", count(State::Active)),
+        format!("{} error", count(State::Error)),
+        format!("{} inactive", count(State::Inactive)),
+    ];
+    for (n, label) in [
+        (count(State::Idle), "idle"),
+        (count(State::Warning), "warning"),
+        (count(State::Unknown), "unknown"),
+        (not_seen, "not seen"),
+        (muted, "muted"),
+    ] {
+        if n > 0 {
+            parts.push(format!("{} {}", n, label));
+        }
+    }
+    let plural = if total == 1 { "" } else { "s" };
+    let mut text = format!(
+        "Total: {} interface{} — {}",
+        total,
+        plural,
+        parts.join(", ")
+    );
+    if stale > 0 {
+        text.push_str(&format!(" ({} stale)", stale));
+    }
+    // The verdict on the whole system leads the line, by the `[banner]` thresholds.
+    let level = view.banner.level(
+        count(State::Inactive),
+        count(State::Warning),
+        count(State::Error),
+    );
+    let (label, color) = match level {
+        Health::Error => ("CRITICAL", Color::Red),
+        Health::Warn => ("DEGRADED", Color::Yellow),
+        _ => ("OK", Color::Green),
+    };
+    let banner = Style::default()
+        .fg(Color::Black)
+        .bg(color)
+        .add_modifier(Modifier::BOLD);
+    Line::from(vec![
+        Span::styled(format!(" {} ", label), banner),
+        Span::raw(" "),
+        Span::raw(text),
+    ])
+}
+
+fn draw_ui<B: Backend>(f: &mut Frame<B>, apps: &[App], view: &mut View, now: Instant) {
+    let selected = view.selected(apps);
+    let detail_lines = match selected {
+        Some((name, interface)) => {
+            let age = format_duration(
+                now.saturating_duration_since(interface.updated),
+                Precision::Coarse,
+            );
+            let seen = format_duration(
+                now.saturating_duration_since(interface.first_seen),
+                Precision::Coarse,
+            );
+            let state = format!("{} ({:?})", interface.value, interface.state);
+            let status = if interface.reported {
+                Line::from(vec![
+                    Span::styled(state, health_style(interface.health())),
+                    Span::raw(format!(", updated {} ago, seen for {}", age, seen)),
+                ])
+            } else {
+                Line::styled("expected, not seen yet", health_style(interface.health()))
+            };
+            let shown = view.name(interface.interface_type, name);
+            let mut lines = vec![
+                Line::from(if view.is_muted(interface.interface_type, name) {
+                    format!("{} ({}, muted)", shown, interface.interface_type)
+                } else {
+                    format!("{} ({})", shown, interface.interface_type)
+                }),
+                status,
+            ];
+            if let Some(message) = &interface.message {
+                lines.push(Line::from(format!("message: {}", message)));
+            }
+            if let Some(rate) = interface.expected_rate {
+                lines.push(rate_line(interface, rate, now));
+            }
+            if !interface.tags.is_empty() {
+                lines.push(Line::from(format!("tags: {}", interface.tags.join(", "))));
+            }
+            match view.prefs.notes.get(name) {
+                Some(_) if view.redact => lines.push(Line::from("note: hidden while redacted")),
+                Some(note) => lines.push(Line::styled(
+                    format!("note: {}", note),
+                    Style::default().fg(Color::Yellow),
+                )),
+                None => {}
+            }
+            for (substate, value) in &interface.substates {
+                lines.push(Line::from(vec![
+                    Span::raw(format!("  {}: ", substate)),
+                    Span::styled(value.clone(), health_style(Health::of_substate(value))),
+                ]));
+            }
+            match &interface.raw {
+                _ if !view.show_payload => {}
+                // The payload names the interface too.
+                Some(_) if view.redact => lines.push(Line::from("payload: hidden while redacted")),
+                Some(raw) => {
+                    lines.push(Line::from("payload:"));
+                    // The rest of the upper half of the screen, the list keeps the other.
+                    let room = (f.size().height / 2).saturating_sub(lines.len() as u16 + 2);
+                    let mut payload = json_lines(raw);
+                    if payload.len() > room as usize {
+                        payload.truncate(room.saturating_sub(1) as usize);
+                        payload.push(Line::from("  …"));
+                    }
+                    lines.extend(payload);
+                }
+                None => lines.push(Line::from("payload: none received")),
+            }
+            lines
+        }
+        None => vec![Line::from("no interface selected")],
+    };
+    let detail_height = detail_lines.len() as u16 + 2;
+    let key_lines = if view.expanded_help {
+        help_table(&view.keymap, f.size().width.saturating_sub(2))
+    } else {
+        Vec::new()
+    };
+    // The totals of every topic, the info line and the keys, between borders.
+    let footer_height = apps.len() as u16 + 3 + key_lines.len() as u16;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(1),
+                Constraint::Length(detail_height),
+                Constraint::Length(footer_height),
+            ]
+            .as_ref(),
+        )
+        .split(f.size());
+
+    let columns = view.columns(apps);
+    view.minimaps.clear();
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Ratio(1, apps.len() as u32); apps.len()])
+        .split(chunks[0]);
+
+    if view.show_history {
+        draw_history(f, chunks[0], &apps[0], view);
+    } else if columns.is_empty() {
+        let block = Block::default().borders(Borders::ALL).title("Interfaces");
+        f.render_widget(block, chunks[0]);
+    } else {
+        for (pane, &area) in panes.iter().enumerate() {
+            let area = match view.topics.get(pane) {
+                Some(topic) => {
+                    let block = Block::default().borders(Borders::ALL).title(topic.as_str());
+                    let inner = block.inner(area);
+                    f.render_widget(block, area);
+                    inner
+                }
+                None => area,
+            };
+            let in_pane: Vec<usize> = (0..columns.len())
+                .filter(|&i| columns[i].pane == pane)
+                .collect();
+            let column_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![
+                    Constraint::Ratio(1, in_pane.len().max(1) as u32);
+                    in_pane.len()
+                ])
+                .split(area);
+            for (&i, &column_area) in in_pane.iter().zip(column_chunks.iter()) {
+                draw_column(f, column_area, view, &columns[i], i, now);
+            }
+        }
+    }
+
+    let detail =
+        Paragraph::new(detail_lines).block(Block::default().borders(Borders::ALL).title("Detail"));
+    f.render_widget(detail, chunks[1]);
+
+    let info_text = if let Some((name, note)) = &view.note {
+        let interface = apps.iter().find_map(|app| app.interface(name));
+        let shown = view.name(interface.map_or("interface", |i| i.interface_type), name);
+        Line::from(vec![
+            Span::raw(format!("note on {}: {}", shown, note)),
+            Span::styled(
+                "  (Enter to save, Esc to cancel)",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])
+    } else if view.editing_filter {
+        let mode = if view.substring_filter {
+            "substring"
+        } else {
+            "fuzzy"
+        };
+        Line::from(vec![
+            Span::raw(format!("/{}", view.filter)),
+            Span::styled(
+                format!("  ({}, Tab to switch)", mode),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])
+    } else {
+        // First, so that a narrow terminal cuts off anything but how to
+        // find the keys.
+        let mut spans = vec![Span::styled(
+            format!(
+                "{} filter  {} help  ",
+                view.keymap.key(Action::Filter),
+                view.keymap.key(Action::Help)
+            ),
+            Style::default().fg(Color::DarkGray),
+        )];
+        if let Some(pause) = &view.paused {
+            spans.push(Span::styled(
+                format!(
+                    " paused: {}, {} resumes ",
+                    pause.reason,
+                    view.keymap.key(Action::Resume)
+                ),
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Red)
+                    .add_modifier(Modifier::BOLD),
+            ));
+            spans.push(Span::raw("  "));
+        }
+        if let Some(toast) = view.toasts.current() {
+            let color = match toast.severity {
+                Severity::Info => Color::Blue,
+                Severity::Warn => Color::Yellow,
+                Severity::Error => Color::Red,
+            };
+            spans.push(Span::styled(
+                format!(" {} ", toast.text),
+                Style::default().fg(Color::White).bg(color),
+            ));
+            spans.push(Span::raw("  "));
+        }
+        // Connected only while every topic is, naming those that aren't.
+        let failing: Vec<&str> = apps
+            .iter()
+            .enumerate()
+            .filter(|(_, app)| app.subscription_error.is_some())
+            .filter_map(|(pane, _)| view.topics.get(pane).map(String::as_str))
+            .collect();
+        spans.push(if !apps.iter().all(|app| app.spinning) {
+            Span::styled("initializing  ", Style::default().fg(Color::DarkGray))
+        } else if apps.iter().any(|app| app.subscription_error.is_some()) {
+            let text = if failing.is_empty() {
+                "not connected  ".to_string()
+            } else {
+                format!("{} not connected  ", failing.join(", "))
+            };
+            Span::styled(text, Style::default().fg(Color::Red))
+        } else {
+            Span::raw("connected  ")
+        });
+        for (pane, app) in apps.iter().enumerate() {
+            let topic = view
+                .topics
+                .get(pane)
+                .map_or(String::new(), |topic| format!("{} ", topic));
+            if let Some(error) = &app.subscription_error {
+                spans.push(Span::styled(
+                    format!("{}subscription failed: {}", topic, error),
+                    Style::default().fg(Color::White).bg(Color::Red),
+                ));
+                spans.push(Span::raw("  "));
+            }
+            if let Some(other) = app.mismatched_encoding {
+                let count = app.stats.encoding_mismatches;
+                let were = if count == 1 {
+                    "message was"
+                } else {
+                    "messages were"
+                };
+                spans.push(Span::styled(
+                    format!(
+                        "{}{} {} {}, check --encoding",
+                        topic,
+                        count,
+                        were,
+                        other.name()
+                    ),
+                    Style::default().fg(Color::Yellow).bg(Color::DarkGray),
+                ));
+                spans.push(Span::raw("  "));
+            }
+            if let Some(count) = app.publisher_count {
+                // Several sources, or data without any advertised source, is worth a look.
+                let style = if count > 1 || (count == 0 && !app.is_empty()) {
+                    Style::default().fg(Color::Yellow).bg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                let plural = if count == 1 { "" } else { "s" };
+                spans.push(Span::styled(
+                    format!("{}{} publisher{}", topic, count, plural),
+                    style,
+                ));
+                spans.push(Span::raw("  "));
+            }
+            // How steadily messages arrive, apart from what they report.
+            if let Some(bars) = app.arrivals.and_then(|arrivals| arrivals.quality(now)) {
+                let color = match bars {
+                    3.. => Color::Green,
+                    2 => Color::Yellow,
+                    _ => Color::Red,
+                };
+                let lit: String = LINK_BARS[..bars].iter().collect();
+                let out: String = std::iter::repeat_n(LINK_BAR_OUT, link::BARS - bars).collect();
+                spans.push(Span::raw(format!("{}link ", topic)));
+                spans.push(Span::styled(lit, Style::default().fg(color)));
+                spans.push(Span::styled(out, Style::default().fg(Color::DarkGray)));
+                spans.push(Span::raw("  "));
+            }
+            if app
+                .last_eviction
+                .is_some_and(|t| now.saturating_duration_since(t) < EVICTION_NOTICE)
+            {
+                spans.push(Span::styled(
+                    format!("{}evicting ({} so far)", topic, app.evictions),
+                    Style::default().fg(Color::Yellow).bg(Color::DarkGray),
+                ));
+                spans.push(Span::raw("  "));
+            }
+        }
+        if !view.muted.is_empty() {
+            // A few are named, more as after muting all problems are counted.
+            let muted = if view.muted_count() <= MUTED_NAMED {
+                let names: Vec<Cow<'_, str>> = view
+                    .muted
+                    .iter()
+                    .flat_map(|(interface_type, names)| {
+                        names.iter().map(|name| view.name(interface_type, name))
+                    })
+                    .collect();
+                names.join(", ")
+            } else {
+                format!("{} muted", view.muted_count())
+            };
+            spans.push(Span::raw(format!(
+                "🔇 {} ({} unmutes)  ",
+                muted,
+                view.keymap.key(Action::UnmuteAll)
+            )));
+        }
+        spans.push(Span::raw(format!(
+            "{} - quit",
+            view.keymap.key(Action::Quit)
+        )));
+        if !view.filter.is_empty() {
+            spans.push(Span::raw(format!("  filter: {}", view.filter)));
+        }
+        if view.follow {
+            spans.push(Span::raw(if view.following(now) {
+                "  following"
+            } else {
+                "  follow paused"
+            }));
+        }
+        spans.push(Span::raw(format!(
+            "  {} {}",
+            view.zone.format(SystemTime::now(), CLOCK_FORMAT),
+            view.zone.label()
+        )));
+        Line::from(spans)
+    };
+    let mut lines: Vec<Line> = apps
+        .iter()
+        .enumerate()
+        .map(|(pane, app)| {
+            let mut line = totals_line(app, view, now);
+            if let Some(topic) = view.topics.get(pane) {
+                line.spans.insert(0, Span::raw(format!("{}: ", topic)));
+            }
+            line
+        })
+        .collect();
+    // While editing, the line starts with what was typed.
+    let typed =
+        (view.note.is_some() || view.editing_filter).then(|| info_text.spans[0].width() as u16);
+    lines.push(info_text);
+    lines.extend(key_lines);
+    let info = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Help"))
+        .style(Style::default().fg(Color::Black).bg(Color::White));
+
+    f.render_widget(info, chunks[2]);
+    if let Some(typed) = typed {
+        // Show where typed characters go: after the filter or the note, on the line below the totals.
+        let x = (chunks[2].x + 1 + typed).min(chunks[2].right().saturating_sub(2));
+        f.set_cursor(x, chunks[2].y + 1 + apps.len() as u16);
+    }
+
+    if view.show_help {
+        let lines: Vec<Line> = view.keymap.help().into_iter().map(Line::from).collect();
+        // As tall as the keys are many, within the screen.
+        let mut area = centered_rect(50, 50, f.size());
+        area.height = (lines.len() as u16 + 2).min(f.size().height);
+        // An inline viewport may start further down the terminal.
+        area.y = f.size().y + (f.size().height - area.height) / 2;
+        let help =
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Keys"));
+        f.render_widget(Clear, area);
+        f.render_widget(help, area);
+    }
+
+    if view.show_report {
+        draw_report(f, apps, view);
+    }
+
+    if let Some(palette) = &view.palette {
+        draw_palette(f, palette, &view.keymap, highlight_style(view));
+    }
+
+    if !view.colors {
+        f.render_widget(StripColors, f.size());
+    } else if let Some(theme) = &view.theme {
+        f.render_widget(Recolor(theme), f.size());
+    }
+    if view.ascii {
+        f.render_widget(AsciiOnly, f.size());
+    }
+}
+
+/// Draws the checklist of the expected interfaces, whether each is up, and
+/// of the interfaces that appeared without being expected.
+fn draw_report<B: Backend>(f: &mut Frame<B>, apps: &[App], view: &View) {
+    let pass = Style::default().fg(Color::Green);
+    let fail = Style::default().fg(Color::Red);
+    let mut lines = Vec::new();
+    let mut expected = 0;
+    let mut up = 0;
+    let mut unexpected = 0;
+    for (pane, app) in apps.iter().enumerate() {
+        if let Some(topic) = view.topics.get(pane) {
+            lines.push(Line::from(topic.as_str()));
+        }
+        for check in app.check_expected() {
+            let name = view.name(check.interface_type, check.name);
+            let (mark, detail) = match check.verdict {
+                Verdict::Up => ("✓", String::new()),
+                Verdict::Missing => ("✗", ": missing".to_string()),
+                Verdict::NotActive => ("✗", format!(": {}", check.value)),
+                Verdict::Unexpected => ("✗", ": not expected".to_string()),
+            };
+            match check.verdict {
+                Verdict::Up => up += 1,
+                Verdict::Unexpected => unexpected += 1,
+                _ => {}
+            }
+            if check.verdict != Verdict::Unexpected {
+                expected += 1;
+            }
+            let style = if mark == "✓" { pass } else { fail };
+            lines.push(Line::styled(
+                format!("{} {} {}{}", mark, check.interface_type, name, detail),
+                style,
+            ));
+        }
+    }
+    if expected == 0 {
+        lines = vec![Line::from("no interfaces are expected, see --expected")];
+    }
+    let title = format!(
+        "Expected: {}/{} up, {} not expected",
+        up, expected, unexpected
+    );
+    // As tall as there are checks, within the screen, telling how many don't fit.
+    let mut area = centered_rect(70, 50, f.size());
+    area.height = (lines.len() as u16 + 2).min(f.size().height);
+    area.y = f.size().y + (f.size().height - area.height) / 2;
+    let room = area.height.saturating_sub(2) as usize;
+    if lines.len() > room && room > 0 {
+        let hidden = lines.len() - room + 1;
+        lines.truncate(room - 1);
+        lines.push(Line::from(format!("… {} more", hidden)));
+    }
+    let report = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(Clear, area);
+    f.render_widget(report, area);
+}
+
+/// Draws the command palette: what is typed, and the actions matching it
+/// with their keys and what they do.
+fn draw_palette<B: Backend>(
+    f: &mut Frame<B>,
+    palette: &Palette,
+    keymap: &Keymap,
+    highlight: Style,
+) {
+    let commands = palette.matches(keymap);
+    let mut items: Vec<ListItem> = commands
+        .iter()
+        .map(|command| {
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:<18}", command.name),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!("{:<8}", command.keys),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(command.description),
+            ]))
+        })
+        .collect();
+    if items.is_empty() {
+        items.push(ListItem::new("no matching action"));
+    }
+    let area = centered_rect(70, 50, f.size());
+    let selected = (!commands.is_empty()).then(|| palette.selected.min(commands.len() - 1));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(": {}", palette.query)),
+        )
+        .highlight_style(highlight);
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(
+        list,
+        area,
+        &mut ListState::default().with_selected(selected),
+    );
+    // Typing goes to the query in the title.
+    let typed = Span::raw(palette.query.as_str()).width() as u16;
+    f.set_cursor(
+        (area.x + 3 + typed).min(area.right().saturating_sub(2)),
+        area.y,
+    );
+}
+
+/// Returns a rectangle of the given percentage size centered within `r`.
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::InterfaceState;
+    use crate::config::{Limits, StateValues};
+    use crate::name_filter::NameFilter;
+
+    fn apps() -> Vec<App> {
+        let app = (0..20).fold(
+            App::new(
+                NameFilter::default(),
+                StateValues::default(),
+                Limits::default(),
+            ),
+            |app, i| {
+                app.update_state(InterfaceState {
+                    name: format!("/interface_{}", i),
+                    interface_type: "server".to_string(),
+                    state: "Active".to_string(),
+                    stamp: None,
+                    substates: None,
+                    message: None,
+                    tags: None,
+                    raw: None,
+                })
+            },
+        );
+        vec![app]
+    }
+
+    fn duration(secs: u64, precision: Precision) -> String {
+        format_duration(Duration::from_secs(secs), precision).to_string()
+    }
+
+    #[test]
+    fn durations_switch_units_at_their_boundaries() {
+        for (secs, coarse, fine) in [
+            (0, "0s", "0s"),
+            (59, "59s", "59s"),
+            (60, "1m", "1m00s"),
+            (3599, "59m", "59m59s"),
+            (3600, "1h00m", "1h00m"),
+            (86340, "23h59m", "23h59m"),
+            (86399, "23h59m", "23h59m"),
+            (86400, "1d00h", "1d00h"),
+        ] {
+            assert_eq!(duration(secs, Precision::Coarse), coarse);
+            assert_eq!(duration(secs, Precision::Fine), fine);
+        }
+    }
+
+    #[test]
+    fn age_labels_change_when_the_next_change_says() {
+        let since = Instant::now();
+        for precision in [Precision::Coarse, Precision::Fine] {
+            for secs in (0..200_000).step_by(7) {
+                let now = since + Duration::from_secs(secs);
+                let change = label_change(since, now, precision);
+                let label = |at: Instant| format_duration(at - since, precision).to_string();
+                assert_eq!(label(change - Duration::from_secs(1)), label(now));
+                assert_ne!(label(change), label(now), "{}s", secs);
+            }
+        }
+    }
+
+    /// Draws the monitor after `keys` onto a viewport that starts lower
+    /// down the terminal, as an inline one does after the terminal grew.
+    fn draw_lower_down(keys: &str) {
+        let area = Rect::new(0, 12, 100, 20);
+        let backend = ratatui::backend::TestBackend::new(100, 32);
+        let mut terminal = Terminal::with_options(
+            backend,
+            TerminalOptions {
+                viewport: Viewport::Fixed(area),
+            },
+        )
+        .unwrap();
+        let mut model = Model::new(UiOptions::default(), 1);
+        let _ = model.update(Event::StateChanged(apps()));
+        for c in keys.chars() {
+            let _ = model.update(Event::Input(KeyCode::Char(c).into()));
+        }
+        model.render(&mut terminal).unwrap();
+    }
+
+    #[test]
+    fn the_help_stays_within_a_viewport_lower_down() {
+        draw_lower_down("?");
+    }
+
+    #[test]
+    fn the_report_stays_within_a_viewport_lower_down() {
+        draw_lower_down("e");
+    }
+
+    #[test]
+    fn aliases_are_stable_and_distinct() {
+        let view = View::default();
+        let names: Vec<_> = (0..200).map(|i| format!("/interface_{}", i)).collect();
+        let aliases: BTreeSet<_> = names
+            .iter()
+            .map(|name| view.alias("server", name))
+            .collect();
+        assert_eq!(aliases.len(), names.len());
+        assert_eq!(view.alias("server", "/interface_7"), "server-#8");
+        assert_eq!(view.alias("server", "/interface_7"), "server-#8");
+        assert_eq!(view.alias("publisher", "/interface_7"), "publisher-#1");
+    }
+
+    fn line_text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn muted_interfaces_are_left_out_of_the_totals() {
+        let apps = apps();
+        let mut view = View {
+            stale: Some(Duration::from_secs(60)),
+            ..View::default()
+        };
+        view.muted
+            .entry("server")
+            .or_default()
+            .insert("/interface_0".to_string());
+        let later = Instant::now() + Duration::from_secs(60);
+        let text = line_text(&totals_line(&apps[0], &view, later));
+        assert!(text.contains("19 active"), "{}", text);
+        assert!(text.contains("1 muted"), "{}", text);
+        assert!(text.contains("(19 stale)"), "{}", text);
+    }
+
+    #[test]
+    fn a_subgroup_takes_its_color_from_the_configured_worst() {
+        let state_values = toml::from_str(
+            r#"error = ["Error"]
+warning = ["Warning"]"#,
+        );
+        let app = App::new(
+            NameFilter::default(),
+            state_values.unwrap(),
+            Limits::default(),
+        );
+        let app = [("/g/error", "Error"), ("/g/warning", "Warning")]
+            .into_iter()
+            .fold(app, |app, (name, state)| {
+                app.update_state(InterfaceState {
+                    name: name.to_string(),
+                    interface_type: "server".to_string(),
+                    state: state.to_string(),
+                    stamp: None,
+                    substates: None,
+                    message: None,
+                    tags: None,
+                    raw: None,
+                })
+            });
+        let apps = [app];
+        // The default order and one ranking warnings over errors.
+        let custom = toml::from_str(r#"order = ["warning", "error"]"#).unwrap();
+        let subgroup_by = Regex::new("^/(g)/").unwrap();
+        for (severity, worst) in [
+            (SeverityOrder::default(), Health::Error),
+            (custom, Health::Warn),
+        ] {
+            let view = View {
+                severity,
+                subgroup_by: Some(subgroup_by.clone()),
+                ..View::default()
+            };
+            let columns = view.columns(&apps);
+            let group = view
+                .entries(&columns[0])
+                .into_iter()
+                .find_map(|entry| match entry {
+                    Entry::Header(group) if group.key == "g" => Some(group),
+                    _ => None,
+                });
+            assert_eq!(group.unwrap().worst, Some(worst));
+        }
+    }
+
+    #[test]
+    fn muting_a_name_of_another_type_leaves_the_interface_counted() {
+        let apps = apps();
+        let mut view = View::default();
+        view.muted
+            .entry("publisher")
+            .or_default()
+            .insert("/interface_0".to_string());
+        let text = line_text(&totals_line(&apps[0], &view, Instant::now()));
+        assert!(text.contains("20 active"), "{}", text);
+        assert!(!view.is_muted("server", "/interface_0"));
+    }
+
+    fn clears(written: &[u8]) -> bool {
+        written.windows(4).any(|bytes| bytes == b"\x1b[2J")
+    }
+
+    #[test]
+    fn forced_redraws_write_only_what_changed() {
+        let mut offscreen = Offscreen::new(apps(), 120, 40).unwrap();
+        let first = offscreen.draw().unwrap();
+        assert!(!clears(&first));
+
+        offscreen.resize();
+        let resized = offscreen.draw().unwrap();
+        assert!(!clears(&resized));
+        assert!(resized.len() * 10 < first.len(), "{} bytes", resized.len());
+
+        offscreen.tick();
+        let ticked = offscreen.draw().unwrap();
+        assert!(!clears(&ticked));
+        assert!(ticked.len() * 10 < first.len(), "{} bytes", ticked.len());
+    }
+
+    #[test]
+    fn a_changed_state_writes_less_than_a_frame() {
+        let mut offscreen = Offscreen::new(apps(), 120, 40).unwrap();
+        let first = offscreen.draw().unwrap();
+        let mut changed = apps();
+        changed[0] = changed[0].clone().update_state(InterfaceState {
+            name: "/interface_3".to_string(),
+            interface_type: "server".to_string(),
+            state: "Error".to_string(),
+            stamp: None,
+            substates: None,
+            message: None,
+            tags: None,
+            raw: None,
+        });
+        offscreen.set_apps(changed);
+        let written = offscreen.draw().unwrap();
+        assert!(!written.is_empty());
+        assert!(written.len() < first.len() / 2, "{} bytes", written.len());
+    }
+}
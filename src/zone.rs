@@ -0,0 +1,79 @@
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use std::time::SystemTime;
+
+/// The time zone wall-clock times are shown in, set with `--tz`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Zone {
+    #[default]
+    Local,
+    Utc,
+    Offset(FixedOffset),
+}
+
+impl Zone {
+    /// Parses "local", "utc" or an offset from UTC such as "+02:00" or "-0530".
+    pub fn parse(text: &str) -> Result<Zone, String> {
+        match text.to_lowercase().as_str() {
+            "local" => return Ok(Zone::Local),
+            "utc" | "z" => return Ok(Zone::Utc),
+            _ => {}
+        }
+        let invalid = || format!("'{}' is not utc, local or an offset like +02:00", text);
+        let (sign, offset) = match text.split_at_checked(1) {
+            Some(("+", offset)) => (1, offset),
+            Some(("-", offset)) => (-1, offset),
+            _ => return Err(invalid()),
+        };
+        let digits = offset.replace(':', "");
+        if digits.len() != 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let (hours, minutes): (i32, i32) =
+            (digits[..2].parse().unwrap(), digits[2..].parse().unwrap());
+        if minutes >= 60 {
+            return Err(invalid());
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+            .map(Zone::Offset)
+            .ok_or_else(invalid)
+    }
+
+    /// Formats a moment with a chrono format string, e.g. "%H:%M:%S".
+    pub fn format(&self, at: SystemTime, format: &str) -> String {
+        let utc = DateTime::<Utc>::from(at);
+        match self {
+            Zone::Local => utc.with_timezone(&Local).format(format).to_string(),
+            Zone::Utc => utc.format(format).to_string(),
+            Zone::Offset(offset) => utc.with_timezone(offset).format(format).to_string(),
+        }
+    }
+
+    /// A short name of the zone, to tell which one times are in.
+    pub fn label(&self) -> String {
+        match self {
+            Zone::Local => "local".to_string(),
+            Zone::Utc => "UTC".to_string(),
+            Zone::Offset(offset) => offset.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_are_parsed_with_or_without_a_colon() {
+        let offset = |seconds| Ok(Zone::Offset(FixedOffset::east_opt(seconds).unwrap()));
+        assert_eq!(Zone::parse("+02:00"), offset(2 * 3600));
+        assert_eq!(Zone::parse("-0530"), offset(-(5 * 3600 + 30 * 60)));
+        assert_eq!(Zone::parse("UTC"), Ok(Zone::Utc));
+    }
+
+    #[test]
+    fn offsets_out_of_range_or_too_short_are_rejected() {
+        for text in ["+24:00", "+2", "+02:60", "02:00", "+02:0a"] {
+            assert!(Zone::parse(text).is_err(), "{}", text);
+        }
+    }
+}